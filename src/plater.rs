@@ -1,13 +1,15 @@
 pub mod bitmap;
+pub mod cancellation;
 pub mod execution_mode;
 pub mod part;
-mod placed_part;
-mod placement;
+pub mod placed_part;
+pub mod placement;
 pub mod placer;
-pub(crate) mod plate;
+pub mod plate;
 pub mod plate_shape;
-pub(crate) mod point;
+pub mod point;
 pub mod progress;
+pub mod quality_map;
 pub mod recommender;
 pub(crate) mod rectangle;
 pub mod request;
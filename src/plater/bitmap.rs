@@ -27,6 +27,34 @@ fn parse_int(input: f64) -> Option<i32> {
     return None;
 }
 
+// DistanceMap holds, for every pixel of a bitmap, the distance in pixels to the
+// nearest occupied pixel or to the bitmap border.
+#[derive(Clone)]
+pub(crate) struct DistanceMap {
+    width: i32,
+    height: i32,
+    data: Vec<f64>,
+}
+
+impl DistanceMap {
+    pub(crate) fn get(&self, x: i32, y: i32) -> f64 {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            0.0
+        } else {
+            self.data[(self.width * y + x) as usize]
+        }
+    }
+
+    // clearance returns the smallest distance in the map under the outline of a bitmap,
+    // when the bitmap is offset by (off_x, off_y).
+    pub(crate) fn clearance(&self, outline: &[(i32, i32)], off_x: i32, off_y: i32) -> f64 {
+        outline
+            .iter()
+            .map(|(x, y)| self.get(x + off_x, y + off_y))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
 pub struct Bitmap {
     // Image dimensions
     pub(crate) width: i32,
@@ -466,6 +494,109 @@ impl Bitmap {
         expanded
     }
 
+    // fill_holes returns a copy of the bitmap with the enclosed holes smaller than
+    // min_pixels filled in. Holes are the empty areas that cannot be reached from
+    // outside the bitmap.
+    pub(crate) fn fill_holes(&self, min_pixels: usize) -> Self {
+        let mut filled = self.clone();
+        let mut seen = vec![false; self.data.len()];
+        let neighbours = |x: i32, y: i32| [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)];
+
+        // Flood the empty area reachable from the border
+        let mut stack = vec![];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let border = x == 0 || y == 0 || x == self.width - 1 || y == self.height - 1;
+                if border && self.at(x, y) == 0 {
+                    seen[self.index(x, y)] = true;
+                    stack.push((x, y));
+                }
+            }
+        }
+        self.flood(&mut seen, &mut stack, neighbours);
+
+        // Whatever empty area is left is made of holes
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.at(x, y) != 0 || seen[self.index(x, y)] {
+                    continue;
+                }
+
+                seen[self.index(x, y)] = true;
+                stack.push((x, y));
+                let hole = self.flood(&mut seen, &mut stack, neighbours);
+                if hole.len() < min_pixels {
+                    for (hx, hy) in hole {
+                        filled.set_point(hx, hy, 1);
+                    }
+                }
+            }
+        }
+
+        filled
+    }
+
+    // flood visits the empty pixels connected to those on the stack that have not
+    // been seen, and returns every pixel visited.
+    fn flood(
+        &self,
+        seen: &mut [bool],
+        stack: &mut Vec<(i32, i32)>,
+        neighbours: impl Fn(i32, i32) -> [(i32, i32); 4],
+    ) -> Vec<(i32, i32)> {
+        let mut visited = vec![];
+        while let Some((x, y)) = stack.pop() {
+            visited.push((x, y));
+            for (nx, ny) in neighbours(x, y) {
+                if nx < 0 || ny < 0 || nx >= self.width || ny >= self.height {
+                    continue;
+                }
+
+                let index = self.index(nx, ny);
+                if !seen[index] && self.at(nx, ny) == 0 {
+                    seen[index] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+
+        visited
+    }
+
+    // sweep returns the area covered by a rectangle that extends left, right, down and
+    // up from a point, as the point moves over every pixel of the bitmap.
+    pub(crate) fn sweep(&self, left: i32, right: i32, down: i32, up: i32) -> Self {
+        let width = self.width + left + right;
+        let height = self.height + down + up;
+
+        // Sweep along X into rows, then sweep the rows along Y
+        let mut rows = vec![0u8; (width * self.height) as usize];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.at(x, y) != 0 {
+                    let base = (y * width) as usize;
+                    rows[base + x as usize..=base + (x + left + right) as usize].fill(1);
+                }
+            }
+        }
+
+        let mut swept = Bitmap::new(width, height);
+        swept.center_x = self.center_x + left as f64;
+        swept.center_y = self.center_y + down as f64;
+
+        for y in 0..self.height {
+            for x in 0..width {
+                if rows[(y * width + x) as usize] != 0 {
+                    for sy in y..=y + down + up {
+                        swept.set_point(x, sy, 1);
+                    }
+                }
+            }
+        }
+
+        swept
+    }
+
     pub fn get_bound(&self) -> (f64, f64, f64, f64) {
         let (width, height) = self.get_dims();
 
@@ -529,4 +660,184 @@ impl Bitmap {
 
         (bottom_space, top_space, left_space, right_space)
     }
+
+    // hull_points returns the corners of the leftmost and rightmost occupied pixel of
+    // every row, which is enough to compute the convex hull of the bitmap.
+    pub(crate) fn hull_points(&self) -> Vec<(f64, f64)> {
+        let mut points = vec![];
+        for y in 0..self.height {
+            let occupied = |x: &i32| self.at(*x, y) != 0;
+            let (first, last) = match (0..self.width).find(occupied) {
+                None => continue,
+                Some(first) => (first, (0..self.width).rev().find(occupied).unwrap()),
+            };
+
+            for x in [first, last + 1] {
+                points.push((x as f64, y as f64));
+                points.push((x as f64, (y + 1) as f64));
+            }
+        }
+
+        points
+    }
+
+    // distance_map computes a 3-4 chamfer distance transform, treating everything
+    // outside the bitmap as occupied.
+    pub(crate) fn distance_map(&self) -> DistanceMap {
+        const STRAIGHT: f64 = 3.0;
+        const DIAGONAL: f64 = 4.0;
+
+        let (width, height) = self.get_dims();
+        let mut data = vec![0.0; (width * height) as usize];
+
+        for y in 0..height {
+            for x in 0..width {
+                if self.at(x, y) == 0 {
+                    let border = min(min(x + 1, y + 1), min(width - x, height - y));
+                    data[self.index(x, y)] = border as f64 * STRAIGHT;
+                }
+            }
+        }
+
+        let forward = [(-1, 0, STRAIGHT), (0, -1, STRAIGHT), (-1, -1, DIAGONAL), (1, -1, DIAGONAL)];
+        let backward = [(1, 0, STRAIGHT), (0, 1, STRAIGHT), (1, 1, DIAGONAL), (-1, 1, DIAGONAL)];
+
+        let mut relax = |x: i32, y: i32, steps: &[(i32, i32, f64); 4]| {
+            let index = self.index(x, y);
+            for (dx, dy, cost) in steps {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width || ny >= height {
+                    continue;
+                }
+                let candidate = data[self.index(nx, ny)] + cost;
+                if candidate < data[index] {
+                    data[index] = candidate;
+                }
+            }
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                relax(x, y, &forward);
+            }
+        }
+
+        for y in (0..height).rev() {
+            for x in (0..width).rev() {
+                relax(x, y, &backward);
+            }
+        }
+
+        DistanceMap {
+            width,
+            height,
+            data: data.into_iter().map(|d| d / STRAIGHT).collect(),
+        }
+    }
+
+    // outline returns the occupied pixels that touch an empty pixel or the border of the
+    // bitmap. Anything outside the bitmap is nearer to one of them than to any other
+    // occupied pixel, so the clearance only needs to look at the outline.
+    pub(crate) fn outline(&self) -> Vec<(i32, i32)> {
+        let empty = |x: i32, y: i32| {
+            x < 0 || y < 0 || x >= self.width || y >= self.height || self.at(x, y) == 0
+        };
+
+        let mut outline = vec![];
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if empty(x, y) {
+                    continue;
+                }
+
+                let touches_empty = (-1..=1)
+                    .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                    .any(|(dx, dy)| empty(x + dx, y + dy));
+                if touches_empty {
+                    outline.push((x, y));
+                }
+            }
+        }
+
+        outline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+
+    #[test]
+    fn distance_map_measures_from_border() {
+        let bitmap = Bitmap::new(5, 5);
+        let map = bitmap.distance_map();
+        assert_eq!(map.get(0, 0), 1.0);
+        assert_eq!(map.get(2, 2), 3.0);
+        assert_eq!(map.get(4, 2), 1.0);
+    }
+
+    #[test]
+    fn distance_map_measures_from_occupied_pixels() {
+        let mut bitmap = Bitmap::new(9, 9);
+        bitmap.set_point(4, 4, 1);
+        let map = bitmap.distance_map();
+        assert_eq!(map.get(4, 4), 0.0);
+        assert_eq!(map.get(5, 4), 1.0);
+        assert_eq!(map.get(4, 6), 2.0);
+
+        let mut part = Bitmap::new(1, 1);
+        part.set_point(0, 0, 1);
+        assert_eq!(map.clearance(&part.outline(), 6, 4), 2.0);
+    }
+
+    #[test]
+    fn outline_skips_interior_pixels() {
+        let mut bitmap = Bitmap::new(4, 3);
+        for y in 0..3 {
+            for x in 0..4 {
+                bitmap.set_point(x, y, 1);
+            }
+        }
+        bitmap.set_point(0, 0, 0);
+
+        let outline = bitmap.outline();
+        assert_eq!(outline.len(), 10);
+        assert!(!outline.contains(&(2, 1)));
+        // The pixel next to the empty corner is on the outline, though it is inside
+        assert!(outline.contains(&(1, 1)));
+    }
+
+    #[test]
+    fn fill_holes_keeps_holes_of_minimum_size() {
+        // A frame with a 2x1 hole and a 1x1 hole
+        let mut bitmap = Bitmap::new(7, 3);
+        for y in 0..3 {
+            for x in 0..7 {
+                bitmap.set_point(x, y, 1);
+            }
+        }
+        bitmap.set_point(1, 1, 0);
+        bitmap.set_point(2, 1, 0);
+        bitmap.set_point(5, 1, 0);
+
+        let filled = bitmap.fill_holes(2);
+        assert_eq!(filled.get_point(5, 1), 1);
+        assert_eq!(filled.get_point(1, 1), 0);
+
+        let filled = bitmap.fill_holes(usize::MAX);
+        assert_eq!(filled.pixels, 21);
+    }
+
+    #[test]
+    fn sweep_covers_rectangle_around_pixels() {
+        let mut bitmap = Bitmap::new(2, 1);
+        bitmap.set_point(0, 0, 1);
+        let swept = bitmap.sweep(1, 2, 0, 3);
+
+        assert_eq!(swept.get_dims(), (5, 4));
+        assert_eq!(swept.pixels, 16);
+        assert_eq!(swept.get_point(4, 3), 0);
+        assert_eq!(swept.get_point(3, 3), 1);
+        assert_eq!(swept.center_x, bitmap.center_x + 1.0);
+    }
 }
@@ -0,0 +1,174 @@
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::pin::{pin, Pin};
+use std::sync::mpsc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::plater::execution_mode::{finish, start_placers};
+use crate::plater::placer::{Placer, N};
+use crate::plater::progress::{ProgressMessage, ProgressMessenger};
+use crate::plater::recommender::{Recommender, Suggestion};
+use crate::plater::request::{PlacingError, Request};
+use crate::plater::solution::Solution;
+
+// AsyncRunner runs the placers one at a time, yielding to the executor between them.
+// It does not depend on any particular executor.
+pub struct AsyncRunner<'r> {
+    request: &'r Request,
+}
+
+// YieldNow is pending the first time it is polled, so that awaiting it lets the
+// executor run other tasks.
+struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+// yield_now returns a future that yields to the executor once.
+pub fn yield_now() -> impl Future<Output = ()> {
+    YieldNow { yielded: false }
+}
+
+async fn place_async<'request_part, F2, F3, Y, YF>(
+    placers: &mut [Placer<'request_part>],
+    messenger: ProgressMessenger<F2>,
+    mut cancellation_future: F3,
+    mut yield_fn: Y,
+) -> Vec<Solution<'request_part>>
+where
+    F2: Fn(ProgressMessage),
+    F3: Future + Unpin,
+    Y: FnMut() -> YF,
+    YF: Future,
+{
+    let mut smallest_plate_index = None;
+    // The placers keep to the deadline themselves
+    let mut rec = Recommender::new(Duration::MAX, placers.len());
+    let rec = &mut rec;
+
+    let total = placers.len();
+
+    messenger.send_message(|| ProgressMessage::PreRun {
+        total_placers: total
+            .try_into()
+            .expect("Could not represent placement count as u32"),
+    });
+
+    let (sender, receiver) = mpsc::channel();
+    let mut results = vec![];
+    'placing_loop: for (index, placer) in placers.iter_mut().enumerate() {
+        messenger.send_message(|| ProgressMessage::Placement {
+            placer_index: index as u32,
+            percentage: index as f64 * 100.00 / total as f64,
+            total_placers: total as u32,
+        });
+        // This line is required to periodically yield to the executor for fairer scheduling
+        let mut yielded = pin!(yield_fn());
+        let cancelled = futures::future::select(&mut cancellation_future, &mut yielded);
+        if let futures::future::Either::Left(_) = cancelled.await {
+            break 'placing_loop;
+        }
+
+        if placer.should_stop() {
+            break 'placing_loop;
+        }
+
+        if let Some(plate_index) = smallest_plate_index {
+            if plate_index <= N {
+                break 'placing_loop;
+            }
+        }
+
+        match rec.observe(smallest_plate_index) {
+            Suggestion::Stop => break 'placing_loop,
+            Suggestion::Continue => {}
+        }
+
+        placer.smallest_observed_plate = smallest_plate_index;
+        placer.progress_sender = Some(sender.clone());
+
+        // Place a step at a time, yielding and passing on progress between steps
+        let solution = loop {
+            let step = placer.step();
+            for message in receiver.try_iter() {
+                messenger.forward(message);
+            }
+
+            match step {
+                ControlFlow::Break(solution) => break solution,
+                ControlFlow::Continue(()) => {
+                    let mut yielded = pin!(yield_fn());
+                    let cancelled = futures::future::select(&mut cancellation_future, &mut yielded);
+                    if let futures::future::Either::Left(_) = cancelled.await {
+                        break 'placing_loop;
+                    }
+                }
+            }
+        };
+
+        // Update the best solution if we found something better
+        if let Some(solution) = solution {
+            messenger.send_message(|| ProgressMessage::SolutionFound {
+                placer_index: index as u32,
+                solution: Box::new(solution.clone()),
+            });
+
+            smallest_plate_index = solution.best_so_far;
+            results.push(solution)
+        }
+    }
+
+    results
+}
+
+impl<'r> AsyncRunner<'r> {
+    pub fn new(request: &'r Request) -> Self {
+        AsyncRunner { request }
+    }
+
+    // place stops placing once cancellation_future completes, and yields with
+    // yield_now.
+    pub async fn place<F: Future, F2: Fn(ProgressMessage)>(
+        &self,
+        messenger: ProgressMessenger<F2>,
+        cancellation_future: F,
+    ) -> Result<Solution<'r>, PlacingError> {
+        self.place_with_yield(messenger, cancellation_future, yield_now)
+            .await
+    }
+
+    // place_with_yield awaits the future returned by yield_fn between placers, for
+    // executors that need a yield of their own.
+    pub async fn place_with_yield<F, F2, Y, YF>(
+        &self,
+        messenger: ProgressMessenger<F2>,
+        cancellation_future: F,
+        yield_fn: Y,
+    ) -> Result<Solution<'r>, PlacingError>
+    where
+        F: Future,
+        F2: Fn(ProgressMessage),
+        Y: FnMut() -> YF,
+        YF: Future,
+    {
+        let pinned_future = Box::pin(cancellation_future);
+        let (mut placers, deadline) = start_placers(self.request);
+        let mut solutions = place_async(&mut placers, messenger, pinned_future, yield_fn).await;
+
+        finish(self.request, &mut solutions, deadline)
+    }
+}
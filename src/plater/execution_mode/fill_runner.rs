@@ -0,0 +1,186 @@
+use crate::plater::execution_mode::{deadline, is_past};
+use crate::plater::part::Part;
+use crate::plater::placer::Placer;
+use crate::plater::request::{PlacingError, Request};
+use crate::plater::solution::Solution;
+
+// FillRunner places as many copies of a prioritised list of parts as fit on a
+// single, fixed plate, around any locked or pinned parts of the request. Copies of a
+// part are told apart by the instance of their placements. The request timeout
+// applies as it does to the other runners, and a fill stopped by it is time limited.
+pub struct FillRunner<'r> {
+    request: &'r Request,
+}
+
+// Fill is the result of filling a plate.
+pub struct Fill<'r> {
+    pub solution: Solution<'r>,
+    // counts holds the number of copies placed of each filler, in priority order
+    counts: Vec<(String, usize)>,
+}
+
+impl<'r> Fill<'r> {
+    pub fn get_counts(&self) -> &[(String, usize)] {
+        self.counts.as_slice()
+    }
+
+    // get_count returns the number of copies placed of the given part.
+    pub fn get_count(&self, id: &str) -> usize {
+        self.counts
+            .iter()
+            .find(|(filler, _)| filler == id)
+            .map_or(0, |(_, count)| *count)
+    }
+
+    pub fn total_count(&self) -> usize {
+        self.counts.iter().map(|(_, count)| count).sum()
+    }
+}
+
+impl<'r> FillRunner<'r> {
+    pub fn new(request: &'r Request) -> Self {
+        FillRunner { request }
+    }
+
+    // fill fills the plate with the parts with the given IDs. The plate is filled with
+    // copies of the first part before the remaining space is filled with the next.
+    pub fn fill(&self, ids: &[&str]) -> Result<Fill<'r>, PlacingError> {
+        let mut fillers: Vec<&'r Part> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let part = self
+                .request
+                .parts
+                .get(*id)
+                .ok_or_else(|| PlacingError::UnknownPart(id.to_string()))?;
+
+            if !fillers.iter().any(|filler| filler.id == part.id) {
+                fillers.push(part);
+            }
+        }
+
+        let mut placer = Placer::new(self.request);
+        placer.deadline = deadline(self.request);
+        let (mut solution, counts) = placer
+            .fill(&fillers)
+            .ok_or(PlacingError::NoSolutionFound)?;
+        // The plate is only partly filled if filling was cancelled
+        if self.request.is_cancelled() {
+            return Err(PlacingError::Cancelled);
+        }
+        solution.time_limited = is_past(placer.deadline);
+
+        let counts = fillers
+            .iter()
+            .map(|filler| filler.id.clone())
+            .zip(counts)
+            .collect();
+
+        Ok(Fill { solution, counts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::execution_mode::fill_runner::FillRunner;
+    use crate::plater::part::Part;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::request::{
+        Algorithm, BedExpansionMode, ConfigOrder, PointEnumerationMode, Request, Strategy,
+    };
+
+    // request is a 30 by 30 mm plate with a solid square part of each size in pixels,
+    // with the IDs p0, p1 and so on.
+    fn request(sizes: &[i32]) -> Request {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(30.0, 30.0, 1.0);
+        let mut request = Request::new(shape, 1.0, algorithm, 15.0, 15.0);
+        request.set_seed(0);
+        for (i, size) in sizes.iter().enumerate() {
+            let pixels = vec![1; (size * size) as usize];
+            let bitmap = Bitmap::new_bitmap_with_data(*size, *size, &pixels).unwrap();
+            let part = Part::new(
+                format!("p{i}"),
+                bitmap,
+                0.0,
+                0.0,
+                request.precision,
+                request.delta_r,
+                request.spacing,
+                30.0,
+                30.0,
+                false,
+            )
+            .unwrap();
+            request.add_part(part).unwrap();
+        }
+        request
+    }
+
+    #[test]
+    fn fill_places_copies_in_priority_order() {
+        let request = request(&[20, 6]);
+        let runner = FillRunner::new(&request);
+
+        let fill = runner.fill(&["p0", "p1", "p0"]).unwrap();
+        let ids = fill.get_counts().iter().map(|(id, _)| id.as_str());
+        assert_eq!(ids.collect::<Vec<_>>(), ["p0", "p1"]);
+        assert!(fill.get_count("p0") > 0);
+        assert!(fill.get_count("p1") > 0);
+
+        let placements = &fill.solution.get_placements()[0];
+        assert_eq!(placements.len(), fill.total_count());
+        let mut instances = placements
+            .iter()
+            .filter(|placement| placement.get_id() == "p0")
+            .map(|placement| placement.get_instance())
+            .collect::<Vec<_>>();
+        instances.sort();
+        assert_eq!(instances, (0..fill.get_count("p0")).collect::<Vec<_>>());
+
+        // The small part fills the plate first, leaving no room for the large one
+        let fill = runner.fill(&["p1", "p0"]).unwrap();
+        assert!(fill.get_count("p1") > 0);
+        assert_eq!(fill.get_count("p0"), 0);
+    }
+
+    #[test]
+    fn fill_stops_at_the_timeout() {
+        let mut request = request(&[6]);
+        request.set_timeout(Duration::ZERO);
+
+        let fill = FillRunner::new(&request).fill(&["p0"]).unwrap();
+        assert_eq!(fill.total_count(), 0);
+        assert!(fill.solution.is_time_limited());
+    }
+
+    #[test]
+    fn filled_copies_can_be_pinned() {
+        let mut request = request(&[20, 6]);
+        let fill = FillRunner::new(&request).fill(&["p0"]).unwrap();
+        let placements = fill.solution.get_placements();
+        let count = fill.get_count("p0");
+
+        request.pin_plates(&placements).unwrap();
+        // The pinned copies stay where they are, and the rest of the plate is filled
+        let fill = FillRunner::new(&request).fill(&["p0", "p1"]).unwrap();
+        assert_eq!(fill.get_count("p0"), 0);
+        let kept = &fill.solution.get_placements()[0];
+        for placement in &placements[0] {
+            assert!(kept.iter().any(|other| {
+                other.get_id() == "p0"
+                    && other.get_instance() == placement.get_instance()
+                    && other.get_center().get_x() == placement.get_center().get_x()
+                    && other.get_center().get_y() == placement.get_center().get_y()
+            }));
+        }
+        assert_eq!(kept.len(), count + fill.get_count("p1"));
+    }
+}
@@ -1,3 +1,58 @@
+#[cfg(feature = "wasm")]
 pub mod async_js_runner;
+pub mod async_runner;
+pub mod fill_runner;
 pub mod multi_threaded_runner;
 pub mod single_threaded_runner;
+
+use crate::plater::placer::Placer;
+use crate::plater::request::{PlacingError, Request};
+use crate::plater::solution::{get_best_solution, Solution};
+
+// deadline is when the time budget of the request runs out, for a run starting now.
+pub(crate) fn deadline(request: &Request) -> Option<instant::Instant> {
+    request
+        .timeout
+        .and_then(|timeout| instant::Instant::now().checked_add(timeout))
+}
+
+// is_past returns whether the deadline has passed.
+pub(crate) fn is_past(deadline: Option<instant::Instant>) -> bool {
+    deadline.is_some_and(|deadline| instant::Instant::now() >= deadline)
+}
+
+// start_placers makes the placers for a run, with the deadline that the time budget of
+// the request runs out at.
+pub(crate) fn start_placers(request: &Request) -> (Vec<Placer<'_>>, Option<instant::Instant>) {
+    let deadline = deadline(request);
+
+    let mut placers = request.get_placers_for_spiral_place();
+    for placer in &mut placers {
+        placer.deadline = deadline;
+    }
+
+    (placers, deadline)
+}
+
+// finish picks the final solution out of the complete solutions the placers found. The
+// solution is flagged as time limited if the deadline passed during the run.
+pub(crate) fn finish<'r>(
+    request: &Request,
+    solutions: &mut Vec<Solution<'r>>,
+    deadline: Option<instant::Instant>,
+) -> Result<Solution<'r>, PlacingError> {
+    let time_limited = is_past(deadline);
+
+    if solutions.is_empty() {
+        if request.is_cancelled() {
+            return Err(PlacingError::Cancelled);
+        }
+        if time_limited {
+            return Err(PlacingError::TimedOut);
+        }
+    }
+
+    let mut solution = get_best_solution(solutions, &request.objective)?;
+    solution.time_limited = time_limited;
+    Ok(solution)
+}
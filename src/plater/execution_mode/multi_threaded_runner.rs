@@ -1,37 +1,85 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
 use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use rayon::ThreadPool;
 
 use crate::plater::placer::Placer;
 use crate::plater::progress::{ProgressMessage, ProgressMessenger};
 use crate::plater::request::{PlacingError, Request};
-use crate::plater::solution::{get_smallest_solution, Solution};
+use crate::plater::execution_mode::{finish, start_placers};
+use crate::plater::solution::Solution;
 
 pub struct MultiThreadedRunner<'r> {
     request: &'r Request,
 }
 
 fn place_all_multi_threaded<'request, F2: Fn(ProgressMessage)>(
+    pool: &ThreadPool,
     placers: &mut [Placer<'request>],
-    timeout: Option<Duration>,
     messenger: ProgressMessenger<F2>,
 ) -> Vec<Solution<'request>> {
-    let start = &instant::Instant::now();
-    let timeout = &timeout;
-
-    placers
-        .into_par_iter()
-        .filter_map(|placer| {
-            if let Some(limit) = timeout {
-                let now = instant::Instant::now();
-                if now.saturating_duration_since(start.clone()) > *limit {
-                    return None;
-                }
+    let total = placers.len();
+    messenger.send_message(|| ProgressMessage::PreRun {
+        total_placers: total as u32,
+    });
+
+    // The placers send their progress over a channel, as the messenger may not be
+    // shared between threads
+    let (sender, receiver) = mpsc::channel();
+    // The placers share the smallest plate each has found, so the placers after them can
+    // give up on searches that cannot do as well
+    let bounds = Arc::new((0..total).map(|_| AtomicUsize::new(usize::MAX)).collect::<Vec<_>>());
+    for placer in placers.iter_mut() {
+        placer.progress_sender = Some(sender.clone());
+        placer.shared_bounds = Some(bounds.clone());
+    }
+    let started = &AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        let worker = scope.spawn(move || {
+            pool.install(|| {
+                placers
+                    .into_par_iter()
+                    .filter_map(|placer| {
+                        if placer.should_stop() {
+                            return None;
+                        }
+
+                        let index = started.fetch_add(1, Ordering::Relaxed);
+                        let _ = sender.send(ProgressMessage::Placement {
+                            placer_index: placer.index as u32,
+                            percentage: index as f64 * 100.0 / total as f64,
+                            total_placers: total as u32,
+                        });
+
+                        let solution = placer.place()?;
+                        let _ = sender.send(ProgressMessage::SolutionFound {
+                            placer_index: placer.index as u32,
+                            solution: Box::new(solution.clone()),
+                        });
+                        Some(solution)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        });
+
+        while !worker.is_finished() {
+            if let Ok(message) = receiver.recv_timeout(Duration::from_millis(50)) {
+                messenger.forward(message);
             }
+        }
+        for message in receiver.try_iter() {
+            messenger.forward(message);
+        }
 
-            placer.place()
-        })
-        .collect::<Vec<_>>()
+        worker
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
 }
 
 impl<'r> MultiThreadedRunner<'r> {
@@ -42,10 +90,81 @@ impl<'r> MultiThreadedRunner<'r> {
         &self,
         messenger: ProgressMessenger<F2>,
     ) -> Result<Solution<'r>, PlacingError> {
-        let mut placers: Vec<Placer<'r>> = self.request.get_placers_for_spiral_place();
+        let pool = self.request.thread_pool()?;
+        let (mut placers, deadline) = start_placers(self.request);
         let mut solutions: Vec<Solution<'r>> =
-            place_all_multi_threaded(&mut placers, self.request.timeout.clone(), messenger);
+            place_all_multi_threaded(pool, &mut placers, messenger);
+
+        finish(self.request, &mut solutions, deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::execution_mode::multi_threaded_runner::MultiThreadedRunner;
+    use crate::plater::part::Part;
+    use crate::plater::placer::N;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::progress::ProgressMessenger;
+    use crate::plater::request::{
+        Algorithm, BedExpansionMode, ConfigOrder, PointEnumerationMode, Request, Strategy,
+    };
+
+    // request is a seeded request for a plate_size by plate_size mm plate, with a few
+    // solid rectangular parts, and placers that run on several threads.
+    fn request(plate_size: f64) -> Request {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(plate_size, plate_size, 1.0);
+        let center = plate_size / 2.0;
+        let mut request = Request::new(shape, 1.0, algorithm, center, center);
+        request.set_seed(0);
+        // Several threads, so the placers race each other to share their bounds
+        request.set_max_threads(4);
+
+        let sizes = [(40, 30), (30, 30), (30, 20), (20, 20), (20, 10)];
+        for (i, (width, height)) in sizes.into_iter().enumerate() {
+            let pixels = vec![1; (width * height) as usize];
+            let bitmap = Bitmap::new_bitmap_with_data(width, height, &pixels).unwrap();
+            let part = Part::new(
+                format!("p{i}"),
+                bitmap,
+                0.0,
+                0.0,
+                request.precision,
+                request.delta_r,
+                request.spacing,
+                plate_size,
+                plate_size,
+                false,
+            )
+            .unwrap();
+            request.add_part(part).unwrap();
+        }
+        request
+    }
 
-        get_smallest_solution(&mut solutions)
+    #[test]
+    fn repeated_runs_pick_the_same_placer() {
+        // The parts fit on the larger plate, but the smaller one has to be expanded
+        for (plate_size, expanded) in [(100.0, false), (25.0, true)] {
+            let request = request(plate_size);
+            let results = (0..4)
+                .map(|_| {
+                    let solution = MultiThreadedRunner::new(&request)
+                        .place(ProgressMessenger::new(|_| {}))
+                        .unwrap();
+                    let index = solution.best_so_far.unwrap();
+                    assert_eq!(index > N, expanded, "{plate_size}");
+                    format!("{index} {:?}", solution.get_config())
+                })
+                .collect::<Vec<_>>();
+            assert!(results.iter().all(|result| *result == results[0]), "{results:?}");
+        }
     }
 }
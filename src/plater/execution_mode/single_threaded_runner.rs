@@ -1,10 +1,13 @@
+use std::ops::ControlFlow;
+use std::sync::mpsc;
 use std::time::Duration;
 
 use crate::plater::placer::{Placer, N};
 use crate::plater::progress::{ProgressMessage, ProgressMessenger};
 use crate::plater::recommender::{Recommender, Suggestion};
 use crate::plater::request::{PlacingError, Request};
-use crate::plater::solution::{get_smallest_solution, Solution};
+use crate::plater::execution_mode::{finish, start_placers};
+use crate::plater::solution::Solution;
 
 pub struct SingleThreadedRunner<'r> {
     request: &'r Request,
@@ -12,16 +15,20 @@ pub struct SingleThreadedRunner<'r> {
 
 fn place_all_single_threaded<'request, F2: Fn(ProgressMessage)>(
     placers: &mut [Placer<'request>],
-    timeout: Option<Duration>,
     messenger: ProgressMessenger<F2>,
 ) -> Vec<Solution<'request>> {
     let mut smallest_plate_index = None;
-    let max_duration = timeout.unwrap_or_else(|| Duration::from_secs(10));
-    let mut rec = Recommender::new(max_duration, placers.len());
+    // The placers keep to the deadline themselves
+    let mut rec = Recommender::new(Duration::MAX, placers.len());
     let rec = &mut rec;
 
+    let (sender, receiver) = mpsc::channel();
     let mut results = vec![];
     for (index, placer) in placers.iter_mut().enumerate() {
+        if placer.should_stop() {
+            break;
+        }
+
         if let Some(plate_index) = smallest_plate_index.clone() {
             if plate_index <= N {
                 break;
@@ -36,9 +43,22 @@ fn place_all_single_threaded<'request, F2: Fn(ProgressMessage)>(
         }
 
         placer.smallest_observed_plate = smallest_plate_index.clone();
+        placer.progress_sender = Some(sender.clone());
+
+        // Place a step at a time, passing on progress between steps
+        let solution = loop {
+            let step = placer.step();
+            for message in receiver.try_iter() {
+                messenger.forward(message);
+            }
+
+            if let ControlFlow::Break(solution) = step {
+                break solution;
+            }
+        };
 
         // Update the best solution if we found something better
-        if let Some(solution) = placer.place() {
+        if let Some(solution) = solution {
             smallest_plate_index = Option::clone(&solution.best_so_far);
             results.push(solution)
         }
@@ -55,10 +75,65 @@ impl<'r> SingleThreadedRunner<'r> {
         &self,
         messenger: ProgressMessenger<F2>,
     ) -> Result<Solution<'r>, PlacingError> {
-        let mut placers = self.request.get_placers_for_spiral_place();
-        let mut solutions =
-            place_all_single_threaded(&mut placers, self.request.timeout.clone(), messenger);
+        let (mut placers, deadline) = start_placers(self.request);
+        let mut solutions = place_all_single_threaded(&mut placers, messenger);
+
+        finish(self.request, &mut solutions, deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::execution_mode::single_threaded_runner::SingleThreadedRunner;
+    use crate::plater::part::Part;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::progress::{ProgressMessage, ProgressMessenger};
+    use crate::plater::request::{
+        Algorithm, BedExpansionMode, ConfigOrder, PointEnumerationMode, Request, Strategy,
+    };
+
+    #[test]
+    fn placer_progress_reaches_the_messenger() {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(60.0, 60.0, 1.0);
+        let mut request = Request::new(shape, 1.0, algorithm, 30.0, 30.0);
+        request.set_seed(0);
+        for id in ["p0", "p1", "p2"] {
+            let bitmap = Bitmap::new_bitmap_with_data(20, 20, &[1; 400]).unwrap();
+            let part = Part::new(
+                id.to_string(),
+                bitmap,
+                0.0,
+                0.0,
+                request.precision,
+                request.delta_r,
+                request.spacing,
+                60.0,
+                60.0,
+                false,
+            )
+            .unwrap();
+            request.add_part(part).unwrap();
+        }
+
+        let progress = RefCell::new(vec![]);
+        let messenger = ProgressMessenger::new(|message| {
+            if let ProgressMessage::PlacerProgress { parts_placed, total_parts, .. } = message {
+                progress.borrow_mut().push((parts_placed, total_parts));
+            }
+        });
+        SingleThreadedRunner::new(&request).place(messenger).unwrap();
 
-        get_smallest_solution(&mut solutions)
+        let progress = progress.into_inner();
+        assert!(!progress.is_empty());
+        assert!(progress.iter().all(|(placed, total)| placed < total && *total == 3));
     }
 }
@@ -1,8 +1,27 @@
 use std::f64::consts::PI;
+use std::sync::OnceLock;
 
 use itertools::Itertools;
 
 use crate::plater::bitmap::Bitmap;
+use crate::plater::placement::Placement;
+use crate::plater::util;
+
+// ROTATION_STEP_TOLERANCE is how far, in rotation steps, a pinned rotation may be
+// from a whole step, to allow for rounding.
+const ROTATION_STEP_TOLERANCE: f64 = 1e-6;
+
+// Pin fixes a copy of an unlocked part at a previous placement on the given plate.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Pin {
+    pub(crate) plate: usize,
+    // instance is the copy of the part that is pinned, as set by the fill runner
+    pub(crate) instance: usize,
+    // index of the rotated bitmap
+    pub(crate) rotation: usize,
+    pub(crate) center_x: f64,
+    pub(crate) center_y: f64,
+}
 
 pub struct Part {
     pub(crate) locked: bool,
@@ -17,6 +36,33 @@ pub struct Part {
     surface: f64,
     // average bitmap size
     pub(crate) bitmaps: Vec<Bitmap>,
+    // footprint area of the unrotated part, in resolution units squared
+    footprint_area: f64,
+    // footprint area of the part as a fraction of the plate area
+    footprint_fraction: f64,
+    // smallest side of the unrotated footprint, in resolution units: the side in pixels
+    // times the precision, which is the size of a pixel in resolution units
+    footprint_side: f64,
+    // model_height is the height of the part above the plate, in resolution units, as
+    // models are scaled by the resolution when loaded
+    pub(crate) model_height: f64,
+    // volume of the part's model, in resolution units cubed
+    volume: f64,
+    // sensitivity weighs how much the part cares about where on the plate it is printed
+    sensitivity: f64,
+    // slenderness is the ratio of the model height to the smallest side of the footprint
+    slenderness: f64,
+    // area of the convex hull of the unrotated bitmap, in pixels
+    hull_area: f64,
+    // fill_holes fills every hole in the footprint, so no part is nested inside it
+    fill_holes: bool,
+    // footprints, if set, replace the bitmaps with ones that have holes filled, or
+    // include the area the carriage sweeps over while printing the part
+    footprints: Option<Vec<Bitmap>>,
+    // outlines of the bitmaps placing uses, computed on demand for the clearance
+    outlines: OnceLock<Vec<Vec<(i32, i32)>>>,
+    // pins keep copies of the part where they were placed in a previous arrangement
+    pub(crate) pins: Vec<Pin>,
 }
 
 impl Part {
@@ -41,6 +87,7 @@ impl Part {
         }
 
         let (width, height) = trimmed_original.get_dims();
+        let footprint_pixels = trimmed_original.pixels;
 
         // Improvement, we currently only use a rotation if it fits within the original plate
 
@@ -89,7 +136,22 @@ impl Part {
             width: width as f64 + 2.0 * spacing,
             height: height as f64 + 2.0 * spacing,
             surface: 0.0,
+            footprint_area: footprint_pixels as f64 * precision * precision,
+            footprint_fraction: footprint_pixels as f64 * precision * precision
+                / (plate_width * plate_height),
+            footprint_side: i32::min(width, height) as f64 * precision,
+            model_height: 0.0,
+            volume: 0.0,
+            sensitivity: 0.0,
+            slenderness: 0.0,
+            hull_area: 0.0,
+            fill_holes: false,
+            footprints: None,
+            outlines: OnceLock::new(),
+            pins: vec![],
         };
+        p.hull_area = util::polygon_area(&util::convex_hull(p.bitmaps[0].hull_points()));
+        p.update_sensitivity();
 
         let mut correct = 0;
 
@@ -117,6 +179,87 @@ impl Part {
         anyhow::Ok(p)
     }
 
+    // set_model_height records the height of the part's model above the plate.
+    pub fn set_model_height(&mut self, model_height: f64) {
+        self.model_height = model_height;
+        self.update_sensitivity();
+    }
+
+    // set_volume records the volume of the part's model, used to balance the print
+    // volume across plates.
+    pub fn set_volume(&mut self, volume: f64) {
+        self.volume = volume;
+    }
+
+    pub fn get_volume(&self) -> f64 {
+        self.volume
+    }
+
+    pub fn get_footprint_area(&self) -> f64 {
+        self.footprint_area
+    }
+
+    // Large parts are prone to warping and tall, thin parts to wobbling, so both
+    // footprint and slenderness add to the sensitivity.
+    fn update_sensitivity(&mut self) {
+        self.slenderness = if self.footprint_side > 0.0 {
+            self.model_height / self.footprint_side
+        } else {
+            0.0
+        };
+
+        self.sensitivity = self.footprint_fraction + self.slenderness;
+    }
+
+    // get_footprint_fraction returns the footprint area of the part as a fraction of
+    // the plate area.
+    pub(crate) fn get_footprint_fraction(&self) -> f64 {
+        self.footprint_fraction
+    }
+
+    pub(crate) fn get_slenderness(&self) -> f64 {
+        self.slenderness
+    }
+
+    // rotation_index returns the index of the rotated bitmap for a rotation in radians,
+    // or None if the rotation is not a whole number of rotation steps.
+    pub(crate) fn rotation_index(&self, rotation: f64) -> Option<usize> {
+        let steps = rotation / self.delta_r;
+        if f64::abs(steps - f64::round(steps)) > ROTATION_STEP_TOLERANCE {
+            return None;
+        }
+
+        let steps = f64::round(steps) as i64;
+        Some(steps.rem_euclid(self.bitmaps.len() as i64) as usize)
+    }
+
+    // pin fixes the copy of the part given by the placement's instance at the placement
+    // on the given plate. The placement must be at one of the rotations the part can be
+    // placed at.
+    pub(crate) fn pin(&mut self, plate: usize, placement: &Placement) -> Option<()> {
+        let rotation = self.rotation_index(placement.get_rotation())?;
+        let center = placement.get_center();
+        let instance = placement.get_instance();
+
+        self.pins.retain(|pin| pin.instance != instance);
+        self.pins.push(Pin {
+            plate,
+            instance,
+            rotation,
+            center_x: center.get_x(),
+            center_y: center.get_y(),
+        });
+        Some(())
+    }
+
+    pub(crate) fn unpin(&mut self) {
+        self.pins.clear();
+    }
+
+    pub fn get_sensitivity(&self) -> f64 {
+        self.sensitivity
+    }
+
     pub(crate) fn get_id(&self) -> &str {
         &self.id
     }
@@ -127,15 +270,74 @@ impl Part {
     }
 
     pub(crate) fn get_bitmap(&self, index: usize) -> &Bitmap {
-        &self.bitmaps[index]
+        match &self.footprints {
+            Some(bitmaps) => &bitmaps[index],
+            None => &self.bitmaps[index],
+        }
+    }
+
+    // set_fill_holes fills all holes in the footprint of the part, whatever the hole
+    // nesting policy of the request. It takes effect when the part is added.
+    pub fn set_fill_holes(&mut self, fill_holes: bool) {
+        self.fill_holes = fill_holes;
+    }
+
+    pub(crate) fn get_fill_holes(&self) -> bool {
+        self.fill_holes
+    }
+
+    // set_footprints fills the holes smaller than min_hole pixels in every rotation of
+    // the part, then adds the area swept by a carriage, given by its extents in pixels
+    // around the nozzle.
+    pub(crate) fn set_footprints(
+        &mut self,
+        min_hole: usize,
+        keep_out: Option<(i32, i32, i32, i32)>,
+    ) {
+        self.outlines = OnceLock::new();
+        if min_hole == 0 && keep_out.is_none() {
+            self.footprints = None;
+            return;
+        }
+
+        let footprints = self
+            .bitmaps
+            .iter()
+            .map(|bmp| {
+                let filled = if min_hole > 0 {
+                    bmp.fill_holes(min_hole)
+                } else {
+                    bmp.clone()
+                };
+
+                match keep_out {
+                    Some((left, right, front, back)) => filled.sweep(left, right, front, back),
+                    None => filled,
+                }
+            })
+            .collect();
+        self.footprints = Some(footprints);
+    }
+
+    // get_outline returns the outline of the bitmap at the given index.
+    pub(crate) fn get_outline(&self, index: usize) -> &[(i32, i32)] {
+        let outlines = self.outlines.get_or_init(|| {
+            (0..self.bitmaps.len())
+                .map(|i| self.get_bitmap(i).outline())
+                .collect()
+        });
+        &outlines[index]
     }
 
     pub(crate) fn get_surface(&self) -> f64 {
         self.surface
     }
 
-    #[allow(dead_code)]
-    fn get_density(&self, index: usize) -> f64 {
+    pub(crate) fn get_hull_area(&self) -> f64 {
+        self.hull_area
+    }
+
+    pub(crate) fn get_density(&self, index: usize) -> f64 {
         let bmp = self.get_bitmap(index);
         let (width, height) = bmp.get_dims();
 
@@ -10,6 +10,10 @@ pub struct PlacedPart<'a> {
     y: f64,
     rotation: i32,
     pub(crate) insertion_index: usize,
+    // instance tells copies of the same part apart
+    pub(crate) instance: usize,
+    // pinned_plate is the plate the part is pinned to, if it keeps a previous placement
+    pub(crate) pinned_plate: Option<usize>,
 }
 
 impl<'a> PlacedPart<'a> {
@@ -20,14 +24,21 @@ impl<'a> PlacedPart<'a> {
             y: 0.0,
             rotation: 0,
             insertion_index: 0,
+            instance: 0,
+            pinned_plate: None,
         }
     }
 
     // get_id returns the ID of the underlying Part.
-    pub(crate) fn get_id(&self) -> &str {
+    pub fn get_id(&self) -> &str {
         self.part.get_id()
     }
 
+    // is_fixed returns whether the part keeps its position when placing.
+    pub(crate) fn is_fixed(&self) -> bool {
+        self.part.locked || self.pinned_plate.is_some()
+    }
+
     pub(crate) fn set_offset(&mut self, x: f64, y: f64) {
         self.x = x;
         self.y = y;
@@ -42,11 +53,11 @@ impl<'a> PlacedPart<'a> {
         self.part
     }
 
-    pub(crate) fn get_x(&self) -> f64 {
+    pub fn get_x(&self) -> f64 {
         self.x
     }
 
-    pub(crate) fn get_y(&self) -> f64 {
+    pub fn get_y(&self) -> f64 {
         self.y
     }
 
@@ -54,22 +65,25 @@ impl<'a> PlacedPart<'a> {
         self.part.get_bitmap(self.rotation as usize)
     }
 
-    fn get_center_x(&self) -> f64 {
+    pub(crate) fn get_outline(&self) -> &[(i32, i32)] {
+        self.part.get_outline(self.rotation as usize)
+    }
+
+    pub fn get_center_x(&self) -> f64 {
         self.x + self.part.precision * self.get_bitmap().center_x
     }
 
-    fn get_center_y(&self) -> f64 {
+    pub fn get_center_y(&self) -> f64 {
         self.y + self.part.precision * self.get_bitmap().center_y
     }
 
     // get_rotation returns the rotation about the Z axis at the Part's center
     // point as placed, in radians.
-    #[allow(dead_code)]
-    fn get_rotation(&self) -> f64 {
+    pub fn get_rotation(&self) -> f64 {
         (self.rotation as f64) * self.part.delta_r
     }
 
-    pub(crate) fn get_surface(&self) -> f64 {
+    pub fn get_surface(&self) -> f64 {
         self.part.get_surface()
     }
 
@@ -92,20 +106,20 @@ impl<'a> PlacedPart<'a> {
         score
     }
 
-    pub(crate) fn get_gx(&self) -> f64 {
+    pub fn get_gx(&self) -> f64 {
         let bmp = self.get_bitmap();
         (bmp.s_x as f64 / bmp.pixels as f64) * self.part.precision
     }
 
-    pub(crate) fn get_gy(&self) -> f64 {
+    pub fn get_gy(&self) -> f64 {
         let bmp = self.get_bitmap();
         (bmp.s_y as f64 / bmp.pixels as f64) * self.part.precision
     }
 
-    pub(crate) fn get_placement(&self) -> Placement {
+    pub fn get_placement(&self) -> Placement {
         let id = self.get_id().to_string();
         let center = Point::new(self.get_center_x(), self.get_center_y());
         let rotation = self.get_rotation();
-        Placement::new(id, center, rotation)
+        Placement::new(id, center, rotation).with_instance(self.instance)
     }
 }
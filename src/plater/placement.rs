@@ -5,6 +5,7 @@ pub struct Placement {
     id: String,
     center: Point,
     rotation: f64,
+    instance: usize,
 }
 
 impl Clone for Placement {
@@ -13,6 +14,7 @@ impl Clone for Placement {
             id: self.id.to_owned(),
             center: Point::clone(&self.center),
             rotation: self.rotation,
+            instance: self.instance,
         }
     }
 }
@@ -23,8 +25,15 @@ impl Placement {
             id,
             center,
             rotation,
+            instance: 0,
         }
     }
+
+    // with_instance sets which copy of the part the placement is for.
+    pub fn with_instance(mut self, instance: usize) -> Self {
+        self.instance = instance;
+        self
+    }
     pub fn get_id(&self) -> String {
         self.id.to_string()
     }
@@ -36,4 +45,10 @@ impl Placement {
     pub fn get_rotation(&self) -> f64 {
         self.rotation
     }
+
+    // get_instance returns which copy of the part the placement is for. Parts are placed
+    // once, as instance 0, except by the fill runner, which numbers the copies it places.
+    pub fn get_instance(&self) -> usize {
+        self.instance
+    }
 }
@@ -1,34 +1,65 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Debug;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
 use std::vec;
 
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use rand::seq::SliceRandom;
 
+use crate::plater::part::Part;
 use crate::plater::placed_part::PlacedPart;
 use crate::plater::placer::GravityMode::{GravityEQ, GravityXY, GravityYX};
 use crate::plater::placer::helpers::find_solution;
 use crate::plater::placer::rect::Rect;
-use crate::plater::placer::search::{Attempts, binary_search, exponential_search_simple};
+use crate::plater::placer::score::AxisTarget;
+use crate::plater::placer::search::{Attempts, binary_search, search_range};
 use crate::plater::plate::Plate;
 use crate::plater::plate_shape::PlateShape;
-use crate::plater::request::{BedExpansionMode, Request};
+use crate::plater::progress::ProgressMessage;
+use crate::plater::request::{
+    BedExpansionMode, Distribution, LayoutMode, LoadMetric, Request, SearchKind, SearchPolicy,
+};
 use crate::plater::solution::Solution;
+use crate::plater::util;
 
 pub(crate) const N: usize = 128;
+// MULTI_PLATE_RETRIES is the number of shuffled orderings tried by a placer when
+// first fit uses more plates than the area lower bound.
+const MULTI_PLATE_RETRIES: usize = 8;
 
-#[derive(Clone, Copy)]
+// Sort modes decide the order in which parts are placed. The "Dec" modes place
+// the largest parts first.
+#[derive(Clone, Copy, Debug)]
 pub enum SortMode {
     // SortSurfaceDec sorts parts in descending order of surface area.
     SurfaceDec,
     // SortSurfaceInc sorts parts in ascending order of surface area.
     SurfaceInc,
-    // SortShuffle sorts parts in random order.
+    // SortShuffle sorts parts in random order, seeded from the request seed and
+    // the given stream number.
     Shuffle(usize),
     WidthDec,
     HeightDec,
+    // PerimeterDec sorts parts by the perimeter of their bounding box.
+    PerimeterDec,
+    // DiagonalDec sorts parts by the diagonal of their bounding box.
+    DiagonalDec,
+    // LongestSideDec sorts parts by the longest side of their bounding box.
+    LongestSideDec,
+    // ConvexHullDec sorts parts by the area of their convex hull.
+    ConvexHullDec,
+    // DensityDec sorts parts by the fraction of their bounding box they fill.
+    DensityDec,
+    // ModelHeightDec sorts parts by the height of their model.
+    ModelHeightDec,
+    // Custom places the part IDs listed by Request::set_custom_order first, in the
+    // given order, followed by any remaining parts in descending order of surface area.
+    Custom,
 }
 
 impl From<SortMode> for usize {
@@ -39,10 +70,89 @@ impl From<SortMode> for usize {
             SortMode::Shuffle(_) => 2,
             SortMode::WidthDec => 3,
             SortMode::HeightDec => 4,
+            SortMode::PerimeterDec => 5,
+            SortMode::DiagonalDec => 6,
+            SortMode::LongestSideDec => 7,
+            SortMode::ConvexHullDec => 8,
+            SortMode::DensityDec => 9,
+            SortMode::ModelHeightDec => 10,
+            SortMode::Custom => 11,
         }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RotateDirection {
+    CounterClockwise,
+    Clockwise,
+}
+
+// Gravity is the target that parts are pulled towards while placing. Corners
+// and edges refer to the bed, and edges pull parts towards their middle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Gravity {
+    // Original is the default, and keeps the placement order from before gravity could
+    // be set: the spiral starts from the far corner of the original plate, and pixel
+    // placement takes the first position that fits. It scores like BottomLeft.
+    Original,
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+    Bottom,
+    Top,
+    Left,
+    Right,
+    Center,
+    // Point pulls parts towards an attractor point, in mm from the bottom left
+    // corner of the bed.
+    Point(f64, f64),
+}
+
+impl Gravity {
+    // targets returns the pull of the gravity along each axis, for a bed given by
+    // its bottom left corner and size in resolution units.
+    pub(crate) fn targets(
+        self,
+        resolution: f64,
+        bottom_left: (f64, f64),
+        size: (f64, f64),
+    ) -> (AxisTarget, AxisTarget) {
+        let (left, bottom) = bottom_left;
+        let (right, top) = (left + size.0, bottom + size.1);
+        let (mid_x, mid_y) = (left + size.0 / 2.0, bottom + size.1 / 2.0);
+
+        match self {
+            Gravity::Original | Gravity::BottomLeft => {
+                (AxisTarget::Min(left), AxisTarget::Min(bottom))
+            }
+            Gravity::BottomRight => (AxisTarget::Max(right), AxisTarget::Min(bottom)),
+            Gravity::TopLeft => (AxisTarget::Min(left), AxisTarget::Max(top)),
+            Gravity::TopRight => (AxisTarget::Max(right), AxisTarget::Max(top)),
+            Gravity::Bottom => (AxisTarget::Mid(mid_x), AxisTarget::Min(bottom)),
+            Gravity::Top => (AxisTarget::Mid(mid_x), AxisTarget::Max(top)),
+            Gravity::Left => (AxisTarget::Min(left), AxisTarget::Mid(mid_y)),
+            Gravity::Right => (AxisTarget::Max(right), AxisTarget::Mid(mid_y)),
+            Gravity::Center => (AxisTarget::Mid(mid_x), AxisTarget::Mid(mid_y)),
+            Gravity::Point(x, y) => (
+                AxisTarget::Mid(left + x * resolution),
+                AxisTarget::Mid(bottom + y * resolution),
+            ),
+        }
+    }
+}
+
+// PlacerConfig records the configuration of the placer that produced a solution.
+#[derive(Clone, Debug)]
+pub struct PlacerConfig {
+    pub placer_index: usize,
+    pub sort_mode: SortMode,
+    // score_wrapper names the scorer the placer tries first
+    pub score_wrapper: String,
+    pub rotate_direction: RotateDirection,
+    pub gravity: Gravity,
+}
+
 pub enum GravityMode {
     // GravityYX gives Y score a weighting of 10 times the X score.
     GravityYX,
@@ -68,9 +178,11 @@ type PlateId = usize;
 
 #[derive(Clone)]
 pub(crate) struct Placer<'a> {
+    pub(crate) index: usize,
+    sort_mode: SortMode,
     rotate_offset: i32,
-    rotate_direction: i32,
-    // 0 = CCW, 1 = CW, TODO: make an enum
+    rotate_direction: RotateDirection,
+    gravity: Gravity,
     cache: HashMap<PlateId, HashMap<String, bool>>,
 
     // scoring weights
@@ -78,29 +190,111 @@ pub(crate) struct Placer<'a> {
     y_coef: f64,
     // input data
     locked_parts: Vec<PlacedPart<'a>>,
+    // pinned_parts keep their placement from a previous arrangement
+    pinned_parts: Vec<PlacedPart<'a>>,
     pub(crate) unlocked_parts: Vec<PlacedPart<'a>>,
     pub(crate) request: &'a Request,
     // center_x, center_y, width, height
     current_bounding_box: Option<Rect>,
     pub smallest_observed_plate: Option<usize>,
+    // progress_sender receives progress messages while placing, if a runner listens
+    pub(crate) progress_sender: Option<Sender<ProgressMessage<'a>>>,
+    // deadline is when the runner's time budget runs out
+    pub(crate) deadline: Option<instant::Instant>,
+    // plate_search holds the search for the smallest plate between steps
+    plate_search: Option<PlateSearch<'a>>,
+    // shared_bounds holds the smallest plate index found by each of the placers running
+    // in parallel with this one, by placer index, usize::MAX until one is found
+    pub(crate) shared_bounds: Option<Arc<Vec<AtomicUsize>>>,
+}
+
+// PlateSearch is the state of the search for the smallest plate in single plate mode.
+#[derive(Clone)]
+struct PlateSearch<'a> {
+    original_shape: Box<dyn PlateShape>,
+    smallest_observed: Option<usize>,
+    bottom_left: (f64, f64),
+    lowest_index: usize,
+    highest_index: usize,
+    attempts: HashMap<usize, Attempts<Solution<'a>>>,
+}
+
+impl<'a> PlateSearch<'a> {
+    // ends_above returns whether the search is sure to end with a plate larger than the
+    // given index, or with none. Every search only moves on to larger plates after a
+    // failure, so it ends above its smallest plate and above every plate that failed.
+    fn ends_above(&self, index: usize) -> bool {
+        self.lowest_index > index
+            || self.attempts.iter().any(|(attempted, attempt)| {
+                *attempted >= index && matches!(attempt, Attempts::Failure)
+            })
+    }
+
+    // next replays the search over the plate sizes attempted so far. It continues with
+    // the next plate size to attempt, or breaks with the smallest that fits.
+    fn next(&self, policy: &SearchPolicy) -> ControlFlow<Option<usize>, usize> {
+        let mut next = None;
+        let search = |index: usize| match self.attempts.get(&index) {
+            Some(Attempts::Solved(_)) => true,
+            Some(Attempts::Failure) => false,
+            // The rest of the replay is thrown away, so it does not matter what this returns
+            Some(Attempts::ToCompute) | None => {
+                next.get_or_insert(index);
+                false
+            }
+        };
+
+        let lowest_index = self.lowest_index;
+        let smallest = match self.smallest_observed {
+            // With a known upper bound there is nothing to gain from growing the index
+            Some(upper) if policy.kind == SearchKind::Exponential => {
+                binary_search(lowest_index, upper - 1, search)
+            }
+            Some(upper) => search_range(policy.kind, lowest_index, upper - 1, search, None),
+            None => search_range(
+                policy.kind,
+                lowest_index,
+                self.highest_index,
+                search,
+                policy.search_limit,
+            ),
+        };
+
+        match next {
+            Some(index) => ControlFlow::Continue(index),
+            None => ControlFlow::Break(smallest),
+        }
+    }
 }
 
 impl<'a> Placer<'a> {
     pub(crate) fn new(request: &'a Request) -> Self {
         let mut p = Placer {
+            index: 0,
+            sort_mode: SortMode::SurfaceDec,
             rotate_offset: 0,
-            rotate_direction: 0,
+            rotate_direction: RotateDirection::CounterClockwise,
+            gravity: Gravity::Original,
             cache: Default::default(),
             x_coef: 0.0,
             y_coef: 0.0,
             locked_parts: vec![],
+            pinned_parts: vec![],
             unlocked_parts: vec![],
             request,
             current_bounding_box: None,
             smallest_observed_plate: None,
+            progress_sender: None,
+            deadline: None,
+            plate_search: None,
+            shared_bounds: None,
         };
 
-        for part in request.parts.values() {
+        // Visit parts in ID order, as map iteration order varies from run to run
+        let mut parts = request.parts.values().collect::<Vec<_>>();
+        parts.sort_by(|a, b| a.id.cmp(&b.id));
+
+        for part in parts {
             let (off_x, off_y) = (
                 part.center_x - part.width / 2.0,
                 part.center_y - part.height / 2.0,
@@ -110,19 +304,55 @@ impl<'a> Placer<'a> {
             if part.locked {
                 placed_part.set_offset(off_x, off_y);
                 p.locked_parts.push(placed_part)
-            } else {
+            } else if part.pins.is_empty() {
                 p.unlocked_parts.push(placed_part);
+            } else {
+                for pin in &part.pins {
+                    let mut placed_part = placed_part.clone();
+                    placed_part.instance = pin.instance;
+                    placed_part.pinned_plate = Some(pin.plate);
+                    placed_part.set_rotation(pin.rotation as i32);
+                    let bmp = placed_part.get_bitmap();
+                    let (off_x, off_y) = (
+                        pin.center_x - bmp.center_x * part.precision,
+                        pin.center_y - bmp.center_y * part.precision,
+                    );
+                    placed_part.set_offset(off_x, off_y);
+                    p.pinned_parts.push(placed_part)
+                }
             }
         }
 
         p
     }
 
+    // fixed_parts returns the parts that cannot move on the given plate: the locked
+    // parts, which are on every plate, and the parts pinned to it.
+    pub(crate) fn fixed_parts(&self, plate: usize) -> Vec<PlacedPart<'a>> {
+        let pinned = self.pinned_parts.iter().filter(|part| {
+            self.request.single_plate_mode || part.pinned_plate == Some(plate)
+        });
+
+        self.locked_parts.iter().chain(pinned).cloned().collect()
+    }
+
     fn reset_cache(&mut self) {
         self.cache.clear();
     }
 
+    pub(crate) fn set_index(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    // Parts are placed from the back of unlocked_parts, so sorting by an increasing
+    // key places the parts with the largest key first.
+    fn sort_parts_by_key(&mut self, key: impl Fn(&PlacedPart) -> f64) {
+        self.unlocked_parts
+            .sort_by(|x, y| f64::total_cmp(&key(x), &key(y)));
+    }
+
     pub(crate) fn sort_parts(&mut self, sort_mode: SortMode) {
+        self.sort_mode = sort_mode;
         match sort_mode {
             SortMode::SurfaceDec => self.unlocked_parts.sort_by(|x, y| {
                 let s1 = x.get_surface();
@@ -136,8 +366,9 @@ impl<'a> Placer<'a> {
                     f64::partial_cmp(&s2, &s1).unwrap()
                 });
             }
-            SortMode::Shuffle(seed) => {
-                let mut rng = StdRng::seed_from_u64(seed as u64);
+            SortMode::Shuffle(stream) => {
+                let seed = util::derive_seed(self.request.seed, stream as u64);
+                let mut rng = StdRng::seed_from_u64(seed);
                 self.unlocked_parts.shuffle(&mut rng)
             }
             SortMode::WidthDec => {
@@ -149,9 +380,35 @@ impl<'a> Placer<'a> {
             }
             SortMode::HeightDec => {
                 self.unlocked_parts.sort_by(|x, y| {
-                    let s1 = x.part.get_bitmap(0).width;
-                    let s2 = y.part.get_bitmap(0).width;
-                    i32::partial_cmp(&s2, &s1).unwrap()
+                    let s1 = x.part.get_bitmap(0).height;
+                    let s2 = y.part.get_bitmap(0).height;
+                    i32::partial_cmp(&s1, &s2).unwrap()
+                });
+            }
+            SortMode::PerimeterDec => self.sort_parts_by_key(|x| {
+                let (width, height) = x.part.get_bitmap(0).get_dims();
+                2.0 * (width + height) as f64
+            }),
+            SortMode::DiagonalDec => self.sort_parts_by_key(|x| {
+                let (width, height) = x.part.get_bitmap(0).get_dims();
+                f64::hypot(width as f64, height as f64)
+            }),
+            SortMode::LongestSideDec => self.sort_parts_by_key(|x| {
+                let (width, height) = x.part.get_bitmap(0).get_dims();
+                i32::max(width, height) as f64
+            }),
+            SortMode::ConvexHullDec => self.sort_parts_by_key(|x| x.part.get_hull_area()),
+            SortMode::DensityDec => self.sort_parts_by_key(|x| x.part.get_density(0)),
+            SortMode::ModelHeightDec => self.sort_parts_by_key(|x| x.part.model_height),
+            SortMode::Custom => {
+                let order = &self.request.custom_order;
+                self.sort_parts_by_key(|x| x.get_surface());
+                // Unlisted parts keep their position at the front, so they are placed last
+                self.unlocked_parts.sort_by_key(|x| {
+                    order
+                        .iter()
+                        .position(|id| id == x.get_id())
+                        .map_or(0, |position| order.len() - position)
                 });
             }
         }
@@ -168,20 +425,70 @@ impl<'a> Placer<'a> {
         self.y_coef = new_y_coef;
     }
 
-    pub(crate) fn set_rotate_direction(&mut self, direction: i32) {
+    pub(crate) fn set_rotate_direction(&mut self, direction: RotateDirection) {
         self.rotate_direction = direction;
     }
 
+    // set_gravity sets the target parts are pulled towards. Pixel placement weighs the
+    // distance from the target for every gravity but the original one, which keeps
+    // taking the first position that fits.
+    pub(crate) fn set_gravity(&mut self, gravity: Gravity) {
+        self.gravity = gravity;
+        if gravity != Gravity::Original {
+            self.set_gravity_mode(GravityEQ);
+        }
+    }
+
+    // gravity_targets returns the pull of the placer's gravity along each axis.
+    pub(crate) fn gravity_targets(&self) -> (AxisTarget, AxisTarget) {
+        let shape = &self.request.plate_shape;
+        let (width, height) = (shape.width(), shape.height());
+        let bottom_left = (
+            self.request.center_x - width / 2.0,
+            self.request.center_y - height / 2.0,
+        );
+
+        self.gravity
+            .targets(self.request.resolution, bottom_left, (width, height))
+    }
+
     pub(crate) fn set_rotate_offset(&mut self, offset: i32) {
         self.rotate_offset = offset;
     }
 
+    // should_stop returns whether placing should stop, because the request was
+    // cancelled or the deadline has passed.
+    pub(crate) fn should_stop(&self) -> bool {
+        self.request.is_cancelled()
+            || self
+                .deadline
+                .is_some_and(|deadline| instant::Instant::now() >= deadline)
+    }
+
+    // report_progress sends how many of the parts have been placed so far.
+    pub(crate) fn report_progress(
+        &self,
+        search_index: Option<usize>,
+        parts_placed: usize,
+        total_parts: usize,
+    ) {
+        if let Some(sender) = &self.progress_sender {
+            // The runner may have stopped listening, which is fine
+            let _ = sender.send(ProgressMessage::PlacerProgress {
+                placer_index: self.index as u32,
+                search_index,
+                parts_placed,
+                total_parts,
+            });
+        }
+    }
+
     fn place_single_plate_linear<'b>(&'b mut self) -> Option<Solution<'a>> {
         let mut shape = Clone::clone(&self.request.plate_shape);
         let mut plate = Plate::make_plate_with_placed_parts(
             shape.as_ref(),
             self.request.precision,
-            &mut Vec::clone(&self.locked_parts),
+            &mut self.fixed_parts(0),
             self.request.center_x,
             self.request.center_y,
         )?;
@@ -191,15 +498,27 @@ impl<'a> Placer<'a> {
         }
 
         let mut expansion_needed = false;
-        let expand_mm = 10.0;
+        let mut unplaced = vec![];
+        let mut expansion_steps = 0;
+        let total = self.unlocked_parts.len();
         while !self.unlocked_parts.is_empty() {
+            if self.should_stop() {
+                return None;
+            }
+
             if expansion_needed {
+                // Once the plate cannot grow any further the parts will never all fit
+                if expansion_steps >= self.request.expansion_steps() {
+                    return None;
+                }
+
                 // Expand and try again
-                shape = shape.extend_right(expand_mm);
+                expansion_steps += 1;
+                shape = self.request.expanded_plate_shape(expansion_steps);
                 plate = Plate::make_plate_with_placed_parts(
                     shape.as_ref(),
                     self.request.precision,
-                    &mut Vec::clone(&self.locked_parts),
+                    &mut self.fixed_parts(0),
                     self.request.center_x,
                     self.request.center_y,
                 )?;
@@ -213,17 +532,26 @@ impl<'a> Placer<'a> {
             // }
 
             while let Some(cur_part) = self.unlocked_parts.pop() {
+                // Parts that would take the plate over a limit are left off it
+                if !self.within_limits(&plate, &cur_part) {
+                    unplaced.push(cur_part);
+                    continue;
+                }
+
                 match self.place_unlocked_part(&mut plate, cur_part) {
-                    None => {}
+                    None => self.report_progress(
+                        Some(N + expansion_steps),
+                        total - self.unlocked_parts.len(),
+                        total,
+                    ),
                     Some(part) => {
                         self.reset_cache();
                         self.unlocked_parts.push(part);
-                        // Reclaim all parts
+                        self.unlocked_parts.append(&mut unplaced);
+                        // Reclaim the placed parts, the fixed ones are added again with the next plate
                         for part in &mut plate.parts.drain(..) {
-                            if !part.part.locked {
+                            if !part.is_fixed() {
                                 self.unlocked_parts.push(part)
-                            } else {
-                                self.locked_parts.push(part);
                             }
                         }
                         self.unlocked_parts
@@ -238,79 +566,167 @@ impl<'a> Placer<'a> {
 
         let mut solution = Solution::new();
         solution.add_plate(plate);
+        solution.unplaced = unplaced
+            .iter()
+            .map(|part| part.get_id().to_string())
+            .collect();
         Some(solution)
     }
 
-    fn place_single_plate_exp<'b>(&'b mut self) -> Option<Solution<'a>> {
+    // shared_bound returns the smallest plate index found so far by the placers that
+    // come before this one.
+    pub(crate) fn shared_bound(&self) -> Option<usize> {
+        let bounds = self.shared_bounds.as_ref()?;
+        let bound = bounds[..self.index]
+            .iter()
+            .map(|bound| bound.load(Ordering::Relaxed))
+            .min()
+            .unwrap_or(usize::MAX);
+        (bound != usize::MAX).then_some(bound)
+    }
+
+    // start_plate_search sets up the search for the smallest plate in single plate mode.
+    fn start_plate_search(&mut self) -> PlateSearch<'a> {
         let original_shape = Clone::clone(&self.request.plate_shape);
 
         for (i, part) in self.unlocked_parts.iter_mut().enumerate() {
             part.insertion_index = i;
         }
 
-        let res = Clone::clone(&self.smallest_observed_plate);
-
         let bottom_left = (
-            self.request.center_x - original_shape.width() / (2.0 * self.request.precision),
-            self.request.center_y - original_shape.height() / (2.0 * self.request.precision),
+            self.request.center_x - original_shape.width() / 2.0,
+            self.request.center_y - original_shape.height() / 2.0,
         );
 
-        let mut hash_map: HashMap<usize, Attempts<Solution>> = HashMap::new();
-        let mut search = {
-            let original_shape = &original_shape;
-            let res = &res;
-            let hash_map = &mut hash_map;
-            |search_index: usize| {
-                match hash_map.get(&search_index) {
-                    Some(Attempts::Failure | Attempts::Solved(_)) => {}
-                    Some(Attempts::ToCompute) | None => {
-                        let solution =
-                            find_solution(search_index, original_shape, res, self, bottom_left);
-                        let res = match solution {
-                            None => Attempts::Failure,
-                            Some(sol) => Attempts::Solved(sol),
-                        };
-                        hash_map.insert(search_index, res);
+        // Spread layouts use the whole plate, and contracting the plate would cut off
+        // pinned parts, so the plate is never contracted for either
+        let lowest_index = match self.request.layout_mode {
+            _ if !self.pinned_parts.is_empty() => N,
+            LayoutMode::Compact => 1,
+            LayoutMode::Spread => N,
+        };
+
+        PlateSearch {
+            original_shape,
+            smallest_observed: self.smallest_observed_plate,
+            bottom_left,
+            lowest_index,
+            highest_index: N + self.request.expansion_steps(),
+            attempts: HashMap::new(),
+        }
+    }
+
+    // plate_search_step tries one plate size, and breaks with the solution once the
+    // search is over.
+    fn plate_search_step(&mut self) -> ControlFlow<Option<Solution<'a>>> {
+        let mut search = match self.plate_search.take() {
+            Some(search) => search,
+            None => self.start_plate_search(),
+        };
+
+        // The search gives up once it can only end with a larger plate than an earlier
+        // placer found. It is not narrowed to the bound, as the plates it tries would
+        // then depend on when the other threads found theirs. So a placer returns either
+        // the same plate as when running alone, or nothing, which only changes the result
+        // for objectives other than the plate size, where timing can then decide which
+        // placers are given up on.
+        if self.shared_bound().is_some_and(|bound| search.ends_above(bound)) {
+            return ControlFlow::Break(None);
+        }
+
+        match search.next(&self.request.search_policy) {
+            ControlFlow::Continue(index) => {
+                let solution = find_solution(
+                    index,
+                    &search.original_shape,
+                    &search.smallest_observed,
+                    self,
+                    search.bottom_left,
+                );
+                let attempt = match solution {
+                    None => Attempts::Failure,
+                    Some(sol) => {
+                        if let Some(shared) = &self.shared_bounds {
+                            shared[self.index].fetch_min(index, Ordering::Relaxed);
+                        }
+                        Attempts::Solved(sol)
                     }
                 };
+                search.attempts.insert(index, attempt);
+                self.plate_search = Some(search);
+                ControlFlow::Continue(())
+            }
+            ControlFlow::Break(index) => ControlFlow::Break(
+                index.and_then(|index| search.attempts.remove(&index)?.into()),
+            ),
+        }
+    }
 
-                match hash_map.get(&search_index) {
-                    Some(Attempts::Failure) => false,
-                    Some(Attempts::Solved(_)) => true,
-                    _ => panic!("Case should have been handled"),
-                }
+    // place_multi_plate places parts first fit, retrying with shuffled orderings of
+    // the parts until the plate count meets the area lower bound.
+    fn place_multi_plate<'b>(&'b mut self) -> Option<Solution<'a>> {
+        let parts = std::mem::take(&mut self.unlocked_parts);
+        let lower_bound = self.plate_count_lower_bound(&parts);
+
+        let mut best = self.place_first_fit(parts.clone())?;
+        for attempt in 0..MULTI_PLATE_RETRIES {
+            if best.unplaced.is_empty() && best.count_plates() <= lower_bound {
+                break;
             }
-        };
 
-        let smaller = if let Some(upper) = res {
-            binary_search(1, upper - 1, &mut search)
-        } else {
-            exponential_search_simple(N + 1 + 50, &mut search, Some(5))
-        };
+            let stream = self.index * MULTI_PLATE_RETRIES + attempt;
+            let seed = util::derive_seed(self.request.seed, stream as u64);
+            let mut shuffled = parts.clone();
+            shuffled.shuffle(&mut StdRng::seed_from_u64(seed));
 
-        if let Some(mut index) = smaller {
-            return hash_map.remove(&mut index).unwrap().into();
+            if let Some(solution) = self.place_first_fit(shuffled) {
+                if (solution.unplaced.len(), solution.count_plates())
+                    < (best.unplaced.len(), best.count_plates())
+                {
+                    best = solution;
+                }
+            }
         }
 
-        None
+        Some(best)
     }
 
-    fn place_multi_plate<'b>(&'b mut self) -> Option<Solution<'a>> {
+    // pinned_plate_count is the number of plates that parts are pinned to.
+    fn pinned_plate_count(&self) -> usize {
+        self.pinned_parts
+            .iter()
+            .filter_map(|part| part.pinned_plate)
+            .map(|plate| plate + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // plate_count_lower_bound is the number of plates the parts need going by their
+    // area alone, and at least the number of plates with pinned parts.
+    fn plate_count_lower_bound(&self, parts: &[PlacedPart]) -> usize {
+        let shape = &self.request.plate_shape;
+        let plate_area = shape.width() * shape.height() / f64::powi(self.request.precision, 2);
+        let part_area: f64 = parts.iter().map(|part| part.get_surface()).sum();
+        let pinned_count = usize::max(1, self.pinned_plate_count());
+
+        usize::max(f64::ceil(part_area / plate_area) as usize, pinned_count)
+    }
+
+    // place_first_fit places each part, in order, on the first plate it fits on.
+    fn place_first_fit(&mut self, parts: Vec<PlacedPart<'a>>) -> Option<Solution<'a>> {
         let mut solution = Solution::new();
+        self.reset_cache();
+        self.current_bounding_box = None;
 
-        let plate_shape = Clone::clone(&self.request.plate_shape);
-        let plate = Plate::make_plate_with_placed_parts(
-            plate_shape.as_ref(),
-            self.request.precision,
-            &mut Vec::clone(&self.locked_parts),
-            self.request.center_x,
-            self.request.center_y,
-        )?;
-        solution.add_plate(plate);
+        // Start with a plate for each plate that has pinned parts
+        let plate_count = usize::max(1, self.pinned_plate_count());
+        for i in 0..plate_count {
+            solution.add_plate(self.make_multi_plate(i)?);
+        }
 
-        let mut unlocked_parts = vec![];
-        std::mem::swap(&mut unlocked_parts, &mut self.unlocked_parts);
-        for part in unlocked_parts {
+        let total = parts.len();
+        for (placed, part) in parts.into_iter().enumerate() {
+            self.report_progress(None, placed, total);
             let mut i = 0;
             let mut current_part = part;
             while i < solution.count_plates() {
@@ -322,18 +738,14 @@ impl<'a> Placer<'a> {
                     }
                     Some(part) => {
                         if i + 1 == solution.count_plates() {
-                            let shape = Clone::clone(&self.request.plate_shape);
-
-                            // Multi plates and ownership of locked parts
-                            let next_plate = Plate::make_plate_with_placed_parts(
-                                shape.as_ref(),
-                                self.request.precision,
-                                &mut Vec::clone(&self.locked_parts),
-                                self.request.center_x,
-                                self.request.center_y,
-                            )
-                                .unwrap();
-                            solution.add_plate(next_plate);
+                            // Only open a new plate if the part fits on it, otherwise
+                            // the part can never be placed
+                            let mut next_plate = self.make_multi_plate(i + 1)?;
+                            match self.place_unlocked_part(&mut next_plate, part) {
+                                None => solution.add_plate(next_plate),
+                                Some(part) => solution.unplaced.push(part.get_id().to_string()),
+                            }
+                            break;
                         }
                         current_part = part;
                     }
@@ -342,24 +754,556 @@ impl<'a> Placer<'a> {
             }
         }
 
-        self.unlocked_parts.clear();
+        // A placement cut short by the deadline leaves parts off the plates
+        if self.should_stop() {
+            return None;
+        }
+
         Some(solution)
     }
 
+    // place_multi_plate_balanced places each part on the least loaded plate it fits
+    // on, so the plates end up with similar loads.
+    fn place_multi_plate_balanced<'b>(&'b mut self, metric: LoadMetric) -> Option<Solution<'a>> {
+        let plate_count = match self.request.plate_count {
+            Some(count) => count,
+            None => Clone::clone(self).place_multi_plate()?.count_plates(),
+        };
+        let pinned_count = self.pinned_plate_count();
+
+        let mut plates = vec![];
+        let mut loads = vec![];
+        for i in 0..usize::max(1, usize::max(plate_count, pinned_count)) {
+            let plate = self.make_multi_plate(i)?;
+            loads.push(plate_load(&plate, metric));
+            plates.push(plate);
+        }
+        // Each plate keeps its own bounding box, as parts move between plates
+        let mut boxes = vec![None; plates.len()];
+        let mut unplaced = vec![];
+
+        let total = self.unlocked_parts.len();
+        while let Some(part) = self.unlocked_parts.pop() {
+            self.report_progress(None, total - self.unlocked_parts.len() - 1, total);
+            let mut order = (0..plates.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| f64::total_cmp(&loads[*a], &loads[*b]).then(a.cmp(b)));
+
+            let mut current_part = Some(part);
+            for i in order {
+                self.current_bounding_box = boxes[i];
+                current_part = self.place_unlocked_part(&mut plates[i], current_part?);
+                if current_part.is_none() {
+                    boxes[i] = self.current_bounding_box;
+                    loads[i] = plate_load(&plates[i], metric);
+                    break;
+                }
+            }
+
+            // The part does not fit on any plate, so it gets a plate of its own
+            if let Some(part) = current_part {
+                let mut plate = self.make_multi_plate(plates.len())?;
+                self.current_bounding_box = None;
+                match self.place_unlocked_part(&mut plate, part) {
+                    None => {
+                        boxes.push(self.current_bounding_box);
+                        loads.push(plate_load(&plate, metric));
+                        plates.push(plate);
+                    }
+                    Some(part) => unplaced.push(part.get_id().to_string()),
+                }
+            }
+        }
+
+        // A placement cut short by the deadline leaves parts off the plates
+        if self.should_stop() {
+            return None;
+        }
+
+        let mut solution = Solution::new();
+        for plate in plates {
+            solution.add_plate(plate);
+        }
+        solution.unplaced = unplaced;
+        Some(solution)
+    }
+
+    // within_limits returns whether the part can be added to the plate without going
+    // over any of the plate limits.
+    pub(crate) fn within_limits(&self, plate: &Plate, part: &PlacedPart) -> bool {
+        let limits = &self.request.plate_limits;
+        let sum = |load: fn(&Part) -> f64| -> f64 {
+            plate.parts.iter().map(|placed| load(placed.part)).sum::<f64>() + load(part.part)
+        };
+
+        limits
+            .max_parts
+            .is_none_or(|max| plate.count_parts() < max)
+            && limits
+                .max_volume
+                .is_none_or(|max| sum(Part::get_volume) <= max)
+            && limits
+                .max_footprint
+                .is_none_or(|max| sum(Part::get_footprint_area) <= max)
+    }
+
+    // place_multi_plate_height_grouped places the tallest parts first, each on the first
+    // plate whose tallest part is not much taller, so plates hold parts of similar height.
+    fn place_multi_plate_height_grouped<'b>(
+        &'b mut self,
+        max_height_ratio: f64,
+    ) -> Option<Solution<'a>> {
+        let pinned_count = usize::max(1, self.pinned_plate_count());
+
+        let mut plates = vec![];
+        for i in 0..pinned_count {
+            plates.push(self.make_multi_plate(i)?);
+        }
+        let mut boxes = vec![None; plates.len()];
+        let mut unplaced = vec![];
+
+        // Parts are placed from the back, and the sort is stable so the sort mode
+        // still orders parts of equal height
+        self.unlocked_parts
+            .sort_by(|x, y| f64::total_cmp(&x.part.model_height, &y.part.model_height));
+
+        let total = self.unlocked_parts.len();
+        while let Some(part) = self.unlocked_parts.pop() {
+            self.report_progress(None, total - self.unlocked_parts.len() - 1, total);
+            let limit = part.part.model_height * max_height_ratio;
+            let mut current_part = Some(part);
+
+            for i in 0..plates.len() {
+                if plate_height(&plates[i]) > limit {
+                    continue;
+                }
+
+                self.current_bounding_box = boxes[i];
+                current_part = self.place_unlocked_part(&mut plates[i], current_part?);
+                if current_part.is_none() {
+                    boxes[i] = self.current_bounding_box;
+                    break;
+                }
+            }
+
+            if let Some(part) = current_part {
+                let mut plate = self.make_multi_plate(plates.len())?;
+                self.current_bounding_box = None;
+                match self.place_unlocked_part(&mut plate, part) {
+                    None => {
+                        boxes.push(self.current_bounding_box);
+                        plates.push(plate);
+                    }
+                    Some(part) => unplaced.push(part.get_id().to_string()),
+                }
+            }
+        }
+
+        // A placement cut short by the deadline leaves parts off the plates
+        if self.should_stop() {
+            return None;
+        }
+
+        let mut solution = Solution::new();
+        for plate in plates {
+            solution.add_plate(plate);
+        }
+        solution.unplaced = unplaced;
+        Some(solution)
+    }
+
+    fn make_multi_plate(&self, index: usize) -> Option<Plate<'a>> {
+        let plate_shape = Clone::clone(&self.request.plate_shape);
+        Plate::make_plate_with_placed_parts(
+            plate_shape.as_ref(),
+            self.request.precision,
+            &mut self.fixed_parts(index),
+            self.request.center_x,
+            self.request.center_y,
+        )
+    }
+
+    // fill places copies of each filler part, in order, on a single fixed plate until
+    // no rotation of the part fits anywhere, and returns the number of copies placed.
+    // Copies are numbered after any pinned copies of the part. Fillers must be distinct,
+    // as failed placements are cached by part ID.
+    pub(crate) fn fill(&mut self, fillers: &[&'a Part]) -> Option<(Solution<'a>, Vec<usize>)> {
+        let plate_shape = Clone::clone(&self.request.plate_shape);
+        let mut plate = Plate::make_plate_with_placed_parts(
+            plate_shape.as_ref(),
+            self.request.precision,
+            &mut self.fixed_parts(0),
+            self.request.center_x,
+            self.request.center_y,
+        )?;
+
+        let mut counts = Vec::with_capacity(fillers.len());
+        for filler in fillers {
+            let first_instance = filler.pins.iter().map(|pin| pin.instance + 1).max();
+            let mut copy = PlacedPart::new_placed_part(filler);
+            copy.instance = first_instance.unwrap_or(0);
+
+            let mut count = 0;
+            // The part is handed back once no rotation of it fits anywhere on the plate
+            while self.place_unlocked_part(&mut plate, copy.clone()).is_none() {
+                copy.instance += 1;
+                count += 1;
+            }
+            counts.push(count);
+        }
+
+        let mut solution = Solution::new();
+        solution.add_plate(plate);
+        solution.seed = Some(self.request.seed);
+        solution.config = Some(self.config());
+        Some((solution, counts))
+    }
+
+    pub(crate) fn config(&self) -> PlacerConfig {
+        PlacerConfig {
+            placer_index: self.index,
+            sort_mode: self.sort_mode,
+            score_wrapper: self.scorer_name(),
+            rotate_direction: self.rotate_direction,
+            gravity: self.gravity,
+        }
+    }
+
     pub(crate) fn place<'b>(&'b mut self) -> Option<Solution<'a>> {
-        if self.request.single_plate_mode {
+        loop {
+            if let ControlFlow::Break(solution) = self.step() {
+                return solution;
+            }
+        }
+    }
+
+    // step places one step at a time, so the caller can yield, report progress or stop
+    // in between. Each step of the plate search in single plate mode tries one plate
+    // size, the other modes place everything in one step.
+    pub(crate) fn step(&mut self) -> ControlFlow<Option<Solution<'a>>> {
+        // Once the plate search has started it is left to finish with what it has
+        if self.plate_search.is_none() && self.should_stop() {
+            return ControlFlow::Break(None);
+        }
+
+        let solution = if self.request.single_plate_mode {
             match self.request.algorithm.bed_expansion_mode {
                 BedExpansionMode::Linear => self.place_single_plate_linear(),
-                BedExpansionMode::Exponential => self.place_single_plate_exp(),
+                BedExpansionMode::Exponential => match self.plate_search_step() {
+                    ControlFlow::Continue(()) => return ControlFlow::Continue(()),
+                    ControlFlow::Break(solution) => solution,
+                },
             }
         } else {
-            self.place_multi_plate()
-        }
+            match self.request.distribution {
+                Distribution::FirstFit => self.place_multi_plate(),
+                Distribution::Balanced(metric) => self.place_multi_plate_balanced(metric),
+                Distribution::HeightGrouped { max_height_ratio } => {
+                    self.place_multi_plate_height_grouped(max_height_ratio)
+                }
+            }
+        };
+
+        ControlFlow::Break(solution.and_then(|solution| self.finish_solution(solution)))
+    }
+
+    fn finish_solution(&self, mut solution: Solution<'a>) -> Option<Solution<'a>> {
+        // Every layout reports its imbalance, so BestBalance can rank any distribution
+        let metric = match self.request.distribution {
+            Distribution::Balanced(metric) => metric,
+            _ => LoadMetric::Footprint,
+        };
+        solution.update_imbalance(metric);
+        solution.anchor_plates(self.request.anchor, self.request.plate_shape.as_ref());
+        solution.search_policy = Some(self.request.search_policy);
+        solution.seed = Some(self.request.seed);
+        solution.config = Some(self.config());
+        Some(solution)
     }
 }
 
+// plate_load is the total load of the parts placed on the plate, other than the
+// locked parts that every plate has.
+pub(crate) fn plate_load(plate: &Plate, metric: LoadMetric) -> f64 {
+    plate
+        .parts
+        .iter()
+        .filter(|part| !part.part.locked)
+        .map(|part| metric.load(part.part))
+        .sum()
+}
+
+// plate_height is the model height of the tallest part on the plate.
+pub(crate) fn plate_height(plate: &Plate) -> f64 {
+    plate
+        .parts
+        .iter()
+        .map(|part| part.part.model_height)
+        .fold(0.0, f64::max)
+}
+
 mod helpers;
 mod rect;
-pub(crate) mod score;
+pub mod score;
 mod search;
 mod strategies;
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::cancellation::CancellationToken;
+    use crate::plater::execution_mode::single_threaded_runner::SingleThreadedRunner;
+    use crate::plater::part::Part;
+    use crate::plater::placer::{Gravity, Placer, SortMode};
+    use crate::plater::plate::Plate;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::progress::ProgressMessenger;
+    use crate::plater::request::{
+        Algorithm, Anchor, BedExpansionMode, ConfigOrder, Distribution, ExpansionDirection,
+        LayoutMode, PlacingError, PointEnumerationMode, Request, Strategy,
+    };
+
+    // rect_part is a solid part of size by size pixels, that fits on a plate of
+    // plate_size mm.
+    fn rect_part(request: &Request, id: &str, size: i32, plate_size: f64) -> Part {
+        let pixels = vec![1; (size * size) as usize];
+        let bitmap = Bitmap::new_bitmap_with_data(size, size, &pixels).unwrap();
+        Part::new(
+            id.to_string(),
+            bitmap,
+            0.0,
+            0.0,
+            request.precision,
+            request.delta_r,
+            request.spacing,
+            plate_size,
+            plate_size,
+            false,
+        )
+        .unwrap()
+    }
+
+    // request is a seeded request for a 60 by 60 mm plate at 1 pixel per mm, with a
+    // solid square part of each size in pixels, with the IDs p0, p1 and so on.
+    fn request(sizes: &[i32]) -> Request {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(60.0, 60.0, 1.0);
+        let mut request = Request::new(shape, 1.0, algorithm, 30.0, 30.0);
+        request.set_seed(0);
+        for (i, size) in sizes.iter().enumerate() {
+            let part = rect_part(&request, &format!("p{i}"), *size, 60.0);
+            request.add_part(part).unwrap();
+        }
+        request
+    }
+
+    #[test]
+    fn pixel_placement_fills_expanded_plates() {
+        let mut request = request(&[80, 80]);
+        request.set_sort_modes(vec![SortMode::SurfaceDec]);
+        request.set_expansion_direction(ExpansionDirection::Proportional);
+
+        let solution = SingleThreadedRunner::new(&request)
+            .place(ProgressMessenger::new(|_| {}))
+            .unwrap();
+        let plate = &solution.get_plates()[0];
+        let (width, height) = plate.get_size();
+        let (center_x, center_y) = plate.get_center();
+        assert!(width > 60.0);
+        for part in &plate.parts {
+            let bmp = part.get_bitmap();
+            assert!(part.get_x() >= center_x - width / 2.0);
+            assert!(part.get_y() >= center_y - height / 2.0);
+            assert!(part.get_x() + bmp.width as f64 * request.precision <= center_x + width / 2.0);
+            assert!(part.get_y() + bmp.height as f64 * request.precision <= center_y + height / 2.0);
+        }
+    }
+
+    #[test]
+    fn expansion_only_attempts_parts_that_fit_once_expanded() {
+        for (direction, size, fits) in [
+            (ExpansionDirection::Right, 80, true),
+            (ExpansionDirection::Right, 160, false),
+            (ExpansionDirection::Up, 160, false),
+            (ExpansionDirection::Proportional, 160, true),
+        ] {
+            let mut request = request(&[]);
+            for id in ["p0", "p1"] {
+                let part = rect_part(&request, id, size, 200.0);
+                request.add_part(part).unwrap();
+            }
+            request.set_sort_modes(vec![SortMode::SurfaceDec]);
+            request.set_expansion_direction(direction);
+
+            let result =
+                SingleThreadedRunner::new(&request).place(ProgressMessenger::new(|_| {}));
+            assert_eq!(result.is_ok(), fits, "{direction:?} {size}");
+        }
+    }
+
+    #[test]
+    fn linear_expansion_gives_up_on_parts_that_never_fit() {
+        let mut request = request(&[]);
+        request.algorithm.bed_expansion_mode = BedExpansionMode::Linear;
+        // The part is too tall for the plate, however far it is expanded to the right
+        let part = rect_part(&request, "p0", 200, 200.0);
+        request.add_part(part).unwrap();
+
+        let result = SingleThreadedRunner::new(&request).place(ProgressMessenger::new(|_| {}));
+        assert!(matches!(result, Err(PlacingError::NoSolutionFound)));
+    }
+
+    #[test]
+    fn first_fit_layouts_report_their_imbalance() {
+        let mut request = request(&[100, 40]);
+        request.set_single_plate_mode(false);
+        request.set_distribution(Distribution::FirstFit);
+
+        let solution = SingleThreadedRunner::new(&request)
+            .place(ProgressMessenger::new(|_| {}))
+            .unwrap();
+        assert_eq!(solution.count_plates(), 2);
+        let areas = ["p0", "p1"].map(|id| request.parts[id].get_footprint_area());
+        assert_eq!(solution.get_imbalance(), areas[0] - areas[1]);
+    }
+
+    #[test]
+    fn cancelling_keeps_complete_solutions() {
+        let mut request = request(&[20, 20]);
+        let token = CancellationToken::new();
+        request.set_cancellation_token(token.clone());
+
+        // A layout finished before the cancellation is still returned
+        let placer = request.get_placers_for_spiral_place().remove(0);
+        let solution = placer.clone().place().unwrap();
+        token.cancel();
+        assert!(placer.finish_solution(solution).is_some());
+
+        let result = SingleThreadedRunner::new(&request).place(ProgressMessenger::new(|_| {}));
+        assert!(matches!(result, Err(PlacingError::Cancelled)));
+    }
+
+    #[test]
+    fn height_grouping_keeps_short_and_tall_parts_apart() {
+        for max_height_ratio in [2.0, 0.5] {
+            let mut request = request(&[10, 10, 10, 10]);
+            for (id, height) in [("p0", 10.0), ("p1", 100.0), ("p2", 10.0), ("p3", 150.0)] {
+                request.parts.get_mut(id).unwrap().set_model_height(height);
+            }
+            request.set_single_plate_mode(false);
+            request.set_distribution(Distribution::HeightGrouped { max_height_ratio });
+
+            let solution = SingleThreadedRunner::new(&request)
+                .place(ProgressMessenger::new(|_| {}))
+                .unwrap();
+            let mut plates = solution
+                .get_plates()
+                .iter()
+                .map(|plate| {
+                    let mut ids = plate.get_print_order();
+                    ids.sort();
+                    ids
+                })
+                .collect::<Vec<_>>();
+            plates.sort();
+            // A ratio below 1 is raised to 1, so parts of the same height share plates
+            let expected = match max_height_ratio {
+                2.0 => vec![vec!["p0", "p2"], vec!["p1", "p3"]],
+                _ => vec![vec!["p0", "p2"], vec!["p1"], vec!["p3"]],
+            };
+            assert_eq!(plates, expected);
+        }
+    }
+
+    #[test]
+    fn gravity_pulls_parts_to_its_corner() {
+        let cases = [(Gravity::BottomLeft, 0.0), (Gravity::TopRight, 60.0)];
+        for ((gravity, corner), strategy) in cases
+            .into_iter()
+            .cartesian_product([Strategy::PixelPack, Strategy::SpiralPlace])
+        {
+            let mut request = request(&[20]);
+            request.algorithm.strategy = strategy;
+            request.set_single_plate_mode(false);
+            request.set_anchor(Anchor::None);
+            request.set_gravities(vec![gravity]);
+
+            let solution = SingleThreadedRunner::new(&request)
+                .place(ProgressMessenger::new(|_| {}))
+                .unwrap();
+            let center = solution.get_placements()[0][0].get_center();
+            assert!(f64::abs(center.get_x() - corner) < 10.0, "{gravity:?}");
+            assert!(f64::abs(center.get_y() - corner) < 10.0, "{gravity:?}");
+        }
+    }
+
+    #[test]
+    fn spirals_start_from_the_gravity_target() {
+        let request = request(&[]);
+        let plate = Plate::new(request.plate_shape.as_ref(), request.precision, 30.0, 30.0);
+        let cases = [
+            (Gravity::Original, (60.0, 60.0)),
+            (Gravity::BottomLeft, (0.0, 0.0)),
+            (Gravity::TopRight, (60.0, 60.0)),
+            (Gravity::Left, (0.0, 30.0)),
+            (Gravity::Point(10.0, 20.0), (10.0, 20.0)),
+        ];
+        for (gravity, origin) in cases {
+            let mut placer = Placer::new(&request);
+            placer.set_gravity(gravity);
+            assert_eq!(placer.spiral_origin(&plate), origin, "{gravity:?}");
+        }
+    }
+
+    #[test]
+    fn spread_layout_keeps_parts_apart() {
+        let mut request = request(&[10, 10]);
+        request.set_sort_modes(vec![SortMode::SurfaceDec]);
+        request.set_layout_mode(LayoutMode::Spread);
+
+        let solution = SingleThreadedRunner::new(&request)
+            .place(ProgressMessenger::new(|_| {}))
+            .unwrap();
+        let placements = &solution.get_placements()[0];
+        let (a, b) = (placements[0].get_center(), placements[1].get_center());
+        let distance = f64::hypot(a.get_x() - b.get_x(), a.get_y() - b.get_y());
+        assert!(distance > 20.0, "{distance}");
+    }
+
+    #[test]
+    fn custom_sort_places_listed_parts_first() {
+        let mut request = request(&[10, 20, 4]);
+        request.set_custom_order(vec![String::from("p2"), String::from("p0")]);
+
+        let mut placer = Placer::new(&request);
+        placer.sort_parts(SortMode::Custom);
+        // Parts are placed from the back
+        let order = placer
+            .unlocked_parts
+            .iter()
+            .rev()
+            .map(|part| part.get_id())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["p2", "p0", "p1"]);
+    }
+
+    #[test]
+    fn only_other_gravities_weigh_pixel_scores() {
+        let request = request(&[]);
+        let mut placer = Placer::new(&request);
+        placer.set_gravity(Gravity::Original);
+        assert_eq!((placer.x_coef, placer.y_coef), (0.0, 0.0));
+
+        for gravity in [Gravity::BottomLeft, Gravity::TopRight] {
+            let mut placer = Placer::new(&request);
+            placer.set_gravity(gravity);
+            assert_eq!((placer.x_coef, placer.y_coef), (1.0, 1.0), "{gravity:?}");
+        }
+    }
+}
@@ -2,25 +2,9 @@ use crate::plater::placed_part::PlacedPart;
 use crate::plater::placer::{N, Placer};
 use crate::plater::plate::Plate;
 use crate::plater::plate_shape::PlateShape;
+use crate::plater::request::{ExpansionDirection, Request};
 use crate::plater::solution::Solution;
 
-const EXPAND_MM: f64 = 5.0; // cutoff index
-
-fn compute_scale_factor(
-    search_index: usize,
-    cutoff: usize,
-    increment_range: usize,
-    max_scale: f64,
-) -> f64 {
-    if search_index <= cutoff {
-        return 1.0;
-    }
-
-    f64::min((cutoff - search_index) as f64 / increment_range as f64, 1.0) * max_scale
-}
-
-const K: usize = 50;
-
 pub(crate) fn find_solution<'a, 'b>(
     search_index: usize,
     original_shape: &Box<dyn PlateShape>,
@@ -35,24 +19,27 @@ pub(crate) fn find_solution<'a, 'b>(
         }
     }
 
-    let mut should_align_to_bed = false;
+    if placer.should_stop() {
+        return None;
+    }
+
     placer.current_bounding_box = None;
 
+    let policy = &placer.request.search_policy;
     let shape = if search_index < N {
-        original_shape.contract((N - search_index) as f64 * EXPAND_MM)?
+        original_shape.contract((N - search_index) as f64 * policy.contraction_step)?
     } else if search_index == N {
         original_shape.clone()
     } else {
-        should_align_to_bed = true;
-        original_shape.extend_right(compute_scale_factor(search_index, N, K, 5.0))
+        placer.request.expanded_plate_shape(search_index - N)
     };
 
     let center = if search_index <= N {
         (placer.request.center_x, placer.request.center_y)
     } else {
         (
-            bottom_left.0 + shape.width() / (2.0 * placer.request.precision),
-            bottom_left.1 + shape.height() / (2.0 * placer.request.precision),
+            bottom_left.0 + shape.width() / 2.0,
+            bottom_left.1 + shape.height() / 2.0,
         )
     };
 
@@ -60,7 +47,7 @@ pub(crate) fn find_solution<'a, 'b>(
     let mut plate = Plate::make_plate_with_placed_parts(
         shape.as_ref(),
         placer.request.precision,
-        &mut Vec::clone(&placer.locked_parts),
+        &mut placer.fixed_parts(0),
         center.0,
         center.1,
     )?;
@@ -69,32 +56,33 @@ pub(crate) fn find_solution<'a, 'b>(
         return None;
         // Add special handling if some parts will never fit
     } else if search_index > N
-        && !all_parts_can_eventually_be_attempted(&unlocked_parts, shape.as_ref())
+        && !all_parts_can_eventually_be_attempted(&unlocked_parts, placer.request)
     {
         return None;
     }
 
+    let mut unplaced = vec![];
+    let total = unlocked_parts.len();
     while let Some(cur_part) = unlocked_parts.pop() {
+        placer.report_progress(Some(search_index), total - unlocked_parts.len() - 1, total);
+
+        // Parts that would take the plate over a limit are left off it
+        if !placer.within_limits(&plate, &cur_part) {
+            unplaced.push(cur_part.get_id().to_string());
+            continue;
+        }
+
         match placer.place_unlocked_part(&mut plate, cur_part) {
             None => {}
             Some(part) => return None,
         }
     }
 
-    // Centering models is only correct if there are no locked parts
-    if placer.locked_parts.is_empty() {
-        if should_align_to_bed {
-            let width = placer.request.plate_shape.width();
-            let height = placer.request.plate_shape.height();
-            plate.align(width, height);
-        } else {
-            plate.center();
-        }
-    }
 
     let mut solution = Solution::new();
     solution.add_plate(plate);
     solution.best_so_far = Some(search_index);
+    solution.unplaced = unplaced;
     Some(solution)
 }
 
@@ -114,19 +102,24 @@ fn all_parts_can_be_attempted(parts: &Vec<PlacedPart>, plate_shape: &dyn PlateSh
         .all(|x| x)
 }
 
-// If for every model, there exists some rotation that fits try it
-fn all_parts_can_eventually_be_attempted(
-    parts: &Vec<PlacedPart>,
-    plate_shape: &dyn PlateShape,
-) -> bool {
-    parts
-        .iter()
-        .map(|part| {
-            part.part
-                .bitmaps
-                .iter()
-                .map(|x| x.height as f64 <= plate_shape.height())
-                .any(|x| x)
+// If for every model, there exists some rotation that fits the plate once it is fully
+// expanded, try it. Bitmap sizes are in pixels, so they are scaled by the precision to
+// compare them with the plate in resolution units.
+fn all_parts_can_eventually_be_attempted(parts: &[PlacedPart], request: &Request) -> bool {
+    let shape = &request.plate_shape;
+    let max_expansion = f64::max(request.search_policy.max_expansion, 1.0);
+    let (width, height) = match request.expansion_direction {
+        ExpansionDirection::Right => (f64::INFINITY, shape.height()),
+        ExpansionDirection::Up => (shape.width(), f64::INFINITY),
+        ExpansionDirection::Proportional => {
+            (shape.width() * max_expansion, shape.height() * max_expansion)
+        }
+    };
+
+    parts.iter().all(|part| {
+        part.part.bitmaps.iter().any(|x| {
+            x.width as f64 * request.precision <= width
+                && x.height as f64 * request.precision <= height
         })
-        .all(|x| x)
+    })
 }
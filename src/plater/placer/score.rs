@@ -1,5 +1,10 @@
-use crate::plater::placer::score::Position::{Inside, Outside};
+use crate::plater::placed_part::PlacedPart;
 use crate::plater::placer::score::Preference::{First, NoPreference, Second};
+use crate::plater::plate::Plate;
+use crate::plater::quality_map::QualityMap;
+
+// SCORE_TERMS is the number of terms in a placement Score.
+pub const SCORE_TERMS: usize = 4;
 
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub(crate) enum Preference {
@@ -24,17 +29,6 @@ pub(crate) trait Prefer: Copy {
     fn compare_prefer(self, other: Self) -> Preference;
 }
 
-impl Prefer for Position {
-    fn compare_prefer(self, other: Self) -> Preference {
-        match (self, other) {
-            (Inside, Inside) => NoPreference,
-            (Outside, Inside) => Second,
-            (Inside, Outside) => First,
-            (Outside, Outside) => NoPreference,
-        }
-    }
-}
-
 impl Prefer for FloatWrapper {
     fn compare_prefer(self, other: Self) -> Preference {
         let FloatWrapper(n) = self;
@@ -53,178 +47,453 @@ impl Prefer for FloatWrapper {
 #[derive(Copy, Clone)]
 pub(crate) struct FloatWrapper(pub(crate) f64);
 
-#[derive(Copy, Clone)]
-pub(crate) enum Position {
+// Position records whether a candidate lies within the original (unexpanded) plate.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Position {
     Inside,
     Outside,
 }
 
-#[derive(Copy, Clone)]
-pub(crate) struct Score {
-    pub(crate) position: Position,
-    pub(crate) moment_of_inertial: FloatWrapper,
-    pub(crate) x_pos: FloatWrapper,
-    pub(crate) y_pos: FloatWrapper,
+impl From<Position> for f64 {
+    fn from(position: Position) -> Self {
+        match position {
+            Position::Inside => 0.0,
+            Position::Outside => 1.0,
+        }
+    }
+}
+
+// Score is a lexicographic placement score: earlier terms take priority over
+// later ones, and lower values are preferred.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Score([f64; SCORE_TERMS]);
+
+impl Score {
+    pub fn new(terms: [f64; SCORE_TERMS]) -> Self {
+        Score(terms)
+    }
+
+    // from_terms builds a score from up to SCORE_TERMS terms, padding the rest with zero.
+    pub fn from_terms(terms: &[f64]) -> Self {
+        let mut padded = [0.0; SCORE_TERMS];
+        for (slot, term) in padded.iter_mut().zip(terms) {
+            *slot = *term;
+        }
+        Score(padded)
+    }
+
+    pub(crate) fn worst() -> Self {
+        Score([f64::INFINITY; SCORE_TERMS])
+    }
+
+    pub fn terms(&self) -> [f64; SCORE_TERMS] {
+        self.0
+    }
 }
 
 impl Prefer for Score {
     fn compare_prefer(self, other: Self) -> Preference {
-        let cmp_position = || self.position.compare_prefer(other.position);
-        let cmp_inertia = || {
-            self.moment_of_inertial
-                .compare_prefer(other.moment_of_inertial)
-        };
-        let cmp_x = || self.x_pos.compare_prefer(other.x_pos);
-        let cmp_y = || self.y_pos.compare_prefer(other.y_pos);
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .fold(NoPreference, |pref, (a, b)| {
+                pref.defer_to(|| FloatWrapper(*a).compare_prefer(FloatWrapper(*b)))
+            })
+    }
+}
+
+// AxisTarget is where gravity pulls parts along one axis, in resolution units.
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum AxisTarget {
+    // Min pulls the low side of the part towards the value.
+    Min(f64),
+    // Max pulls the high side of the part towards the value.
+    Max(f64),
+    // Mid pulls the middle of the part towards the value.
+    Mid(f64),
+}
 
-        NoPreference
-            .defer_to(cmp_position)
-            .defer_to(cmp_x)
-            .defer_to(cmp_y)
-            .defer_to(cmp_inertia)
+impl AxisTarget {
+    // distance from an extent [lo, hi] of a part to the target.
+    pub(crate) fn distance(self, lo: f64, hi: f64) -> f64 {
+        match self {
+            AxisTarget::Min(v) => lo - v,
+            AxisTarget::Max(v) => v - hi,
+            AxisTarget::Mid(v) => f64::abs((lo + hi) / 2.0 - v),
+        }
+    }
+
+    pub(crate) fn value(self) -> f64 {
+        match self {
+            AxisTarget::Min(v) | AxisTarget::Max(v) | AxisTarget::Mid(v) => v,
+        }
     }
 }
 
-pub(crate) trait ScoreWrapper: Prefer + Copy {
-    fn from(score: Score) -> Self;
+// Candidate is a potential placement of a part on a plate. The part already has
+// its offset and rotation applied. Coordinates are in resolution units, and the
+// bounding box (in pixels) covers the parts placed so far plus the candidate.
+pub struct Candidate<'c, 'p> {
+    pub part: &'c PlacedPart<'p>,
+    pub plate: &'c Plate<'p>,
+    pub x: f64,
+    pub y: f64,
+    pub rotation: usize,
+    pub position: Position,
+    pub bounding_width: f64,
+    pub bounding_height: f64,
+    pub(crate) gravity: (AxisTarget, AxisTarget),
 }
 
-#[derive(Copy, Clone)]
-pub(crate) struct DefaultScoreWrapper(Score);
+impl<'c, 'p> Candidate<'c, 'p> {
+    pub fn moment_of_inertia(&self) -> f64 {
+        f64::powf(self.bounding_height, 2.0) + f64::powf(self.bounding_width, 2.0)
+    }
 
-impl Prefer for DefaultScoreWrapper {
-    fn compare_prefer(self, other: Self) -> Preference {
-        let cmp_position = || self.0.position.compare_prefer(other.0.position);
-        let cmp_inertia = || {
-            self.0
-                .moment_of_inertial
-                .compare_prefer(other.0.moment_of_inertial)
-        };
-        let cmp_x = || self.0.x_pos.compare_prefer(other.0.x_pos);
-        let cmp_y = || self.0.y_pos.compare_prefer(other.0.y_pos);
+    // center returns the centre of the part at the candidate position.
+    pub fn center(&self) -> (f64, f64) {
+        (self.part.get_center_x(), self.part.get_center_y())
+    }
 
-        NoPreference
-            .defer_to(cmp_position)
-            .defer_to(cmp_x)
-            .defer_to(cmp_y)
-            .defer_to(cmp_inertia)
+    // gravity_distance returns the distance of the candidate from the gravity target
+    // along each axis, in resolution units.
+    pub fn gravity_distance(&self) -> (f64, f64) {
+        let bmp = self.part.get_bitmap();
+        let precision = self.part.part.precision;
+        let (x_hi, y_hi) = (
+            self.x + bmp.width as f64 * precision,
+            self.y + bmp.height as f64 * precision,
+        );
+
+        (
+            self.gravity.0.distance(self.x, x_hi),
+            self.gravity.1.distance(self.y, y_hi),
+        )
+    }
+
+    // clearance is the distance in pixels from the candidate to the nearest placed
+    // part or plate edge.
+    pub fn clearance(&self) -> f64 {
+        self.plate.clearance(self.part)
     }
 }
 
-impl ScoreWrapper for DefaultScoreWrapper {
-    fn from(score: Score) -> Self {
-        DefaultScoreWrapper(score)
+// PlacementScorer ranks candidate placements of a part. The candidate with the
+// lowest Score is kept.
+pub trait PlacementScorer: Send + Sync {
+    fn score(&self, candidate: &Candidate) -> Score;
+
+    // name identifies the scorer in the configuration recorded on a solution.
+    fn name(&self) -> String {
+        String::from("custom")
+    }
+
+    // prefers returns whether a candidate with the given score beats the best so far.
+    // Terms within 0.1 of each other tie, and ties keep the earlier candidate.
+    fn prefers(&self, score: Score, best: Score) -> bool {
+        best.compare_prefer(score) == Second
     }
 }
 
 #[derive(Copy, Clone)]
-pub(crate) struct ScoreWrapperA(Score);
+pub struct DefaultScoreWrapper;
 
-impl Prefer for ScoreWrapperA {
-    fn compare_prefer(self, other: Self) -> Preference {
-        let cmp_position = || self.0.position.compare_prefer(other.0.position);
-        let cmp_inertia = || {
-            self.0
-                .moment_of_inertial
-                .compare_prefer(other.0.moment_of_inertial)
-        };
-        let cmp_x = || self.0.x_pos.compare_prefer(other.0.x_pos);
-        let cmp_y = || self.0.y_pos.compare_prefer(other.0.y_pos);
+impl PlacementScorer for DefaultScoreWrapper {
+    fn name(&self) -> String {
+        String::from("default")
+    }
 
-        NoPreference
-            .defer_to(cmp_position)
-            .defer_to(cmp_inertia)
-            .defer_to(cmp_x)
-            .defer_to(cmp_y)
+    fn score(&self, candidate: &Candidate) -> Score {
+        let (dx, dy) = candidate.gravity_distance();
+        Score::new([
+            candidate.position.into(),
+            dx,
+            dy,
+            candidate.moment_of_inertia(),
+        ])
     }
 }
 
-impl ScoreWrapper for ScoreWrapperA {
-    fn from(score: Score) -> Self {
-        ScoreWrapperA(score)
+#[derive(Copy, Clone)]
+pub struct ScoreWrapperA;
+
+impl PlacementScorer for ScoreWrapperA {
+    fn name(&self) -> String {
+        String::from("a")
+    }
+
+    fn score(&self, candidate: &Candidate) -> Score {
+        let (dx, dy) = candidate.gravity_distance();
+        Score::new([
+            candidate.position.into(),
+            candidate.moment_of_inertia(),
+            dx,
+            dy,
+        ])
     }
 }
 
 #[derive(Copy, Clone)]
-pub(crate) struct ScoreWrapperB(Score);
+pub struct ScoreWrapperB;
 
-impl Prefer for ScoreWrapperB {
-    fn compare_prefer(self, other: Self) -> Preference {
-        let cmp_position = || self.0.position.compare_prefer(other.0.position);
-        let cmp_inertia = || {
-            self.0
-                .moment_of_inertial
-                .compare_prefer(other.0.moment_of_inertial)
-        };
-        let cmp_x = || self.0.x_pos.compare_prefer(other.0.x_pos);
-        let cmp_y = || self.0.y_pos.compare_prefer(other.0.y_pos);
+impl PlacementScorer for ScoreWrapperB {
+    fn name(&self) -> String {
+        String::from("b")
+    }
+
+    fn score(&self, candidate: &Candidate) -> Score {
+        let (dx, dy) = candidate.gravity_distance();
+        Score::new([
+            candidate.position.into(),
+            dx,
+            candidate.moment_of_inertia(),
+            dy,
+        ])
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ScoreWrapperC;
+
+impl PlacementScorer for ScoreWrapperC {
+    fn name(&self) -> String {
+        String::from("c")
+    }
 
-        NoPreference
-            .defer_to(cmp_position)
-            .defer_to(cmp_x)
-            .defer_to(cmp_inertia)
-            .defer_to(cmp_y)
+    fn score(&self, candidate: &Candidate) -> Score {
+        let (dx, dy) = candidate.gravity_distance();
+        Score::new([
+            candidate.position.into(),
+            dy,
+            candidate.moment_of_inertia(),
+            dx,
+        ])
     }
 }
 
-impl ScoreWrapper for ScoreWrapperB {
-    fn from(score: Score) -> Self {
-        ScoreWrapperB(score)
+#[derive(Copy, Clone)]
+pub struct ScoreWrapperD;
+
+impl PlacementScorer for ScoreWrapperD {
+    fn name(&self) -> String {
+        String::from("d")
+    }
+
+    fn score(&self, candidate: &Candidate) -> Score {
+        let (dx, dy) = candidate.gravity_distance();
+        Score::new([
+            candidate.position.into(),
+            candidate.moment_of_inertia(),
+            dy,
+            dx,
+        ])
     }
 }
 
+// GravityScorer weights the distance of the part's centre of mass from the
+// gravity target, as used by pixel placement.
 #[derive(Copy, Clone)]
-pub(crate) struct ScoreWrapperC(Score);
+pub struct GravityScorer {
+    x_coef: f64,
+    y_coef: f64,
+}
 
-impl Prefer for ScoreWrapperC {
-    fn compare_prefer(self, other: Self) -> Preference {
-        let cmp_position = || self.0.position.compare_prefer(other.0.position);
-        let cmp_inertia = || {
-            self.0
-                .moment_of_inertial
-                .compare_prefer(other.0.moment_of_inertial)
-        };
-        let cmp_x = || self.0.x_pos.compare_prefer(other.0.x_pos);
-        let cmp_y = || self.0.y_pos.compare_prefer(other.0.y_pos);
+impl GravityScorer {
+    pub fn new(x_coef: f64, y_coef: f64) -> Self {
+        GravityScorer { x_coef, y_coef }
+    }
+}
+
+impl PlacementScorer for GravityScorer {
+    fn name(&self) -> String {
+        String::from("gravity")
+    }
 
-        NoPreference
-            .defer_to(cmp_position)
-            .defer_to(cmp_y)
-            .defer_to(cmp_inertia)
-            .defer_to(cmp_x)
+    fn score(&self, candidate: &Candidate) -> Score {
+        let gx = candidate.part.get_gx() + candidate.x;
+        let gy = candidate.part.get_gy() + candidate.y;
+        let dx = candidate.gravity.0.distance(gx, gx);
+        let dy = candidate.gravity.1.distance(gy, gy);
+        Score::from_terms(&[dy * self.y_coef + dx * self.x_coef])
+    }
+
+    // Pixel placement compares gravity scores exactly
+    fn prefers(&self, score: Score, best: Score) -> bool {
+        score.terms() < best.terms()
     }
 }
 
-impl ScoreWrapper for ScoreWrapperC {
-    fn from(score: Score) -> Self {
-        ScoreWrapperC(score)
+// SpreadScorer prefers the candidate with the largest clearance to the parts
+// already placed and to the plate edges, spreading parts out over the plate.
+#[derive(Copy, Clone)]
+pub struct SpreadScorer;
+
+impl PlacementScorer for SpreadScorer {
+    fn name(&self) -> String {
+        String::from("spread")
+    }
+
+    fn score(&self, candidate: &Candidate) -> Score {
+        Score::new([
+            candidate.position.into(),
+            -candidate.clearance(),
+            candidate.y,
+            candidate.x,
+        ])
     }
 }
 
+// ZoneScorer keeps the layout compact while pulling sensitive parts towards the
+// high quality zones of the plate.
 #[derive(Copy, Clone)]
-pub(crate) struct ScoreWrapperD(Score);
+pub struct ZoneScorer<'m> {
+    map: &'m QualityMap,
+    // centre and half size of the plate, in resolution units
+    center: (f64, f64),
+    half_size: (f64, f64),
+    // squared plate diagonal in pixels, to weigh the footprint penalty against the moment
+    // of inertia
+    scale: f64,
+}
 
-impl Prefer for ScoreWrapperD {
-    fn compare_prefer(self, other: Self) -> Preference {
-        let cmp_position = || self.0.position.compare_prefer(other.0.position);
-        let cmp_inertia = || {
-            self.0
-                .moment_of_inertial
-                .compare_prefer(other.0.moment_of_inertial)
-        };
-        let cmp_x = || self.0.x_pos.compare_prefer(other.0.x_pos);
-        let cmp_y = || self.0.y_pos.compare_prefer(other.0.y_pos);
+impl<'m> ZoneScorer<'m> {
+    pub(crate) fn new(
+        map: &'m QualityMap,
+        center: (f64, f64),
+        size: (f64, f64),
+        precision: f64,
+    ) -> Self {
+        let scale = f64::powf(size.0 / precision, 2.0) + f64::powf(size.1 / precision, 2.0);
+        ZoneScorer {
+            map,
+            center,
+            half_size: (size.0 / 2.0, size.1 / 2.0),
+            scale,
+        }
+    }
+}
+
+impl<'m> PlacementScorer for ZoneScorer<'m> {
+    fn name(&self) -> String {
+        String::from("zone")
+    }
 
-        NoPreference
-            .defer_to(cmp_position)
-            .defer_to(cmp_inertia)
-            .defer_to(cmp_y)
-            .defer_to(cmp_x)
+    fn score(&self, candidate: &Candidate) -> Score {
+        let (x, y) = candidate.center();
+        let quality = self.map.quality_at(
+            (x - self.center.0) / self.half_size.0,
+            (y - self.center.1) / self.half_size.1,
+        );
+        let part = candidate.part.part;
+        // Large parts warp, so their footprint is weighed against the whole plate. Slender
+        // parts only wobble, so slenderness is bounded and weighed against the part's own
+        // footprint, and cannot outweigh keeping the layout compact.
+        let bmp = candidate.part.get_bitmap();
+        let part_scale = f64::powi(bmp.width as f64, 2) + f64::powi(bmp.height as f64, 2);
+        let slenderness = part.get_slenderness();
+        let penalty = (1.0 - quality)
+            * (part.get_footprint_fraction() * self.scale
+                + slenderness / (1.0 + slenderness) * part_scale);
+
+        Score::new([
+            candidate.position.into(),
+            candidate.moment_of_inertia() + penalty,
+            candidate.x,
+            candidate.y,
+        ])
     }
 }
 
-impl ScoreWrapper for ScoreWrapperD {
-    fn from(score: Score) -> Self {
-        ScoreWrapperD(score)
+// SPIRAL_SCORERS are the built-in scorers tried, in order, by spiral placement.
+pub(crate) const SPIRAL_SCORERS: [&dyn PlacementScorer; 5] = [
+    &DefaultScoreWrapper,
+    &ScoreWrapperA,
+    &ScoreWrapperB,
+    &ScoreWrapperC,
+    &ScoreWrapperD,
+];
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::part::Part;
+    use crate::plater::placed_part::PlacedPart;
+    use crate::plater::placer::score::{
+        Candidate, DefaultScoreWrapper, GravityScorer, PlacementScorer, Position, Score,
+        ZoneScorer,
+    };
+    use crate::plater::placer::Placer;
+    use crate::plater::plate::Plate;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::quality_map::QualityMap;
+    use crate::plater::request::{
+        Algorithm, BedExpansionMode, ConfigOrder, PointEnumerationMode, Request, Strategy,
+    };
+
+    #[test]
+    fn gravity_scorer_compares_exactly() {
+        let best = Score::from_terms(&[1.0]);
+        let close = Score::from_terms(&[0.95]);
+
+        assert!(GravityScorer::new(1.0, 1.0).prefers(close, best));
+        assert!(!GravityScorer::new(1.0, 1.0).prefers(best, best));
+        assert!(!DefaultScoreWrapper.prefers(close, best));
+        assert!(DefaultScoreWrapper.prefers(Score::from_terms(&[0.5]), best));
+    }
+
+    #[test]
+    fn zone_scorer_keeps_slender_parts_compact() {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(60.0, 60.0, 1.0);
+        let request = Request::new(shape, 1.0, algorithm, 30.0, 30.0);
+        let bitmap = Bitmap::new_bitmap_with_data(10, 10, &[1; 100]).unwrap();
+        let mut part = Part::new(
+            String::from("tall"),
+            bitmap,
+            0.0,
+            0.0,
+            request.precision,
+            request.delta_r,
+            request.spacing,
+            60.0,
+            60.0,
+            false,
+        )
+        .unwrap();
+        part.set_model_height(1000.0);
+
+        let shape = request.plate_shape.as_ref();
+        let plate = Plate::new(shape, request.precision, 30.0, 30.0);
+        let map = QualityMap::Radial(Box::new(|distance| 1.0 - distance));
+        let zone = ZoneScorer::new(&map, (30.0, 30.0), (60.0, 60.0), request.precision);
+        let gravity = Placer::new(&request).gravity_targets();
+
+        let score = |x: f64, y: f64, bounding_size: f64| {
+            let mut placed = PlacedPart::new_placed_part(&part);
+            placed.set_offset(x, y);
+            let candidate = Candidate {
+                part: &placed,
+                plate: &plate,
+                x,
+                y,
+                rotation: 0,
+                position: Position::Inside,
+                bounding_width: bounding_size,
+                bounding_height: bounding_size,
+                gravity,
+            };
+            zone.score(&candidate)
+        };
+
+        let width = part.get_bitmap(0).width as f64;
+        // The centre of the plate is preferred for the same bounding box
+        assert!(zone.prefers(score(27.0, 27.0, width), score(0.0, 0.0, width)));
+        // But not at the cost of a much larger bounding box
+        assert!(zone.prefers(score(0.0, 0.0, width), score(27.0, 27.0, 60.0)));
     }
 }
@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
 use crate::plater::placer::search::Attempts::Solved;
+use crate::plater::request::SearchKind;
 
 #[derive(Clone, Debug)]
 pub(crate) enum Attempts<T> {
@@ -18,6 +19,63 @@ impl<T> Into<Option<T>> for Attempts<T> {
     }
 }
 
+// search_range finds the smallest index in [lo, hi] that run succeeds for, assuming
+// that it succeeds for every index above that too.
+pub(crate) fn search_range(
+    kind: SearchKind,
+    lo: usize,
+    hi: usize,
+    mut run: impl FnMut(usize) -> bool,
+    search_limit: Option<usize>,
+) -> Option<usize> {
+    if lo > hi {
+        return None;
+    }
+
+    match kind {
+        SearchKind::Linear => (lo..=hi).find(|i| run(*i)),
+        SearchKind::Exponential => {
+            let offset = lo - 1;
+            exponential_search_simple(hi + 1 - offset, |i| run(i + offset), search_limit)
+                .map(|i| i + offset)
+        }
+        SearchKind::GoldenSection => golden_section_search(lo, hi, run, search_limit),
+    }
+}
+
+// golden_section_search bisects at the golden ratio rather than the middle, so more of
+// the probes land on the smaller indices that are expected to fail quickly.
+pub(crate) fn golden_section_search(
+    mut lo: usize,
+    mut hi: usize,
+    mut run: impl FnMut(usize) -> bool,
+    limit: Option<usize>,
+) -> Option<usize> {
+    const RATIO: f64 = 0.381_966_011_250_105;
+
+    let mut iter_count = 0;
+    let mut solution = None;
+    while lo <= hi {
+        iter_count += 1;
+        if limit.is_some_and(|lim| iter_count > lim) && solution.is_some() {
+            return solution;
+        }
+
+        let probe = lo + ((hi - lo) as f64 * RATIO) as usize;
+        if run(probe) {
+            solution = Some(probe);
+            if probe == lo {
+                break;
+            }
+            hi = probe - 1;
+        } else {
+            lo = probe + 1;
+        }
+    }
+
+    solution
+}
+
 pub(crate) fn exponential_search_simple(
     limit: usize,
     mut run: impl FnMut(usize) -> bool,
@@ -117,6 +175,21 @@ mod tests1 {
         assert_eq!(exponential_search_simple(1000, f, None), Some(513));
     }
 
+    #[test]
+    fn search_range_finds_smallest_index() {
+        use crate::plater::placer::search::search_range;
+        use crate::plater::request::SearchKind;
+
+        for kind in [
+            SearchKind::Linear,
+            SearchKind::Exponential,
+            SearchKind::GoldenSection,
+        ] {
+            assert_eq!(search_range(kind, 3, 200, |x| x >= 37, None), Some(37));
+            assert_eq!(search_range(kind, 3, 200, |_| false, None), None);
+        }
+    }
+
     #[test]
     fn test4() {
         let f = |x: usize| false;
@@ -4,18 +4,20 @@ use std::f64::consts::PI;
 use crate::plater::placed_part::PlacedPart;
 use crate::plater::placer::rect::Rect;
 use crate::plater::placer::score::Position::{Inside, Outside};
-use crate::plater::placer::score::Prefer;
-use crate::plater::placer::score::Preference::Second;
 use crate::plater::placer::score::{
-    DefaultScoreWrapper, FloatWrapper, Score, ScoreWrapper, ScoreWrapperA, ScoreWrapperB,
-    ScoreWrapperC, ScoreWrapperD,
+    Candidate, GravityScorer, PlacementScorer, Position, Score, SpreadScorer, ZoneScorer,
+    SPIRAL_SCORERS,
 };
 use crate::plater::plate::Plate;
 use crate::plater::plate_shape::PlateShape;
-use crate::plater::request::Strategy;
+use crate::plater::request::{LayoutMode, Request, Strategy};
 use crate::plater::spiral::spiral_iterator;
 
-use super::Placer;
+use super::{Gravity, Placer, RotateDirection};
+
+// STOP_CHECK_INTERVAL is the number of positions tried between checks of the deadline,
+// so that reading the clock does not slow the search down.
+const STOP_CHECK_INTERVAL: usize = 64;
 
 impl<'a> Placer<'a> {
     pub fn place_unlocked_part<'b>(
@@ -23,6 +25,10 @@ impl<'a> Placer<'a> {
         plate: &mut Plate<'b>,
         mut part: PlacedPart<'b>,
     ) -> Option<PlacedPart<'b>> {
+        if !self.within_limits(plate, &part) {
+            return Some(part);
+        }
+
         let cache_name = String::from(part.get_id());
 
         if self.cache.get(&plate.plate_id).is_none() {
@@ -40,28 +46,24 @@ impl<'a> Placer<'a> {
         }
         let rs = f64::ceil(PI * 2.0 / part.part.delta_r) as usize;
 
-        let res =
-            match self.request.algorithm.strategy {
-                Strategy::PixelPack => Placer::pixel_place(self, rs, plate, &mut part),
-                Strategy::SpiralPlace => Placer::spiral_place::<DefaultScoreWrapper>(
-                    self,
-                    rs,
-                    &mut plate.clone(),
-                    &mut part,
-                )
-                .or_else(|| {
-                    Placer::spiral_place::<ScoreWrapperA>(self, rs, &mut plate.clone(), &mut part)
-                })
-                .or_else(|| {
-                    Placer::spiral_place::<ScoreWrapperB>(self, rs, &mut plate.clone(), &mut part)
-                })
-                .or_else(|| {
-                    Placer::spiral_place::<ScoreWrapperC>(self, rs, &mut plate.clone(), &mut part)
-                })
-                .or_else(|| {
-                    Placer::spiral_place::<ScoreWrapperD>(self, rs, &mut plate.clone(), &mut part)
+        let request = self.request;
+        let zone = zone_scorer(request);
+
+        let res = match request.algorithm.strategy {
+            Strategy::PixelPack => {
+                let gravity = GravityScorer::new(self.x_coef, self.y_coef);
+                let scorer = configured_scorer(request, zone.as_ref()).unwrap_or(&gravity);
+                Placer::pixel_place(self, rs, plate, &mut part, scorer)
+            }
+            Strategy::SpiralPlace => match configured_scorer(request, zone.as_ref()) {
+                Some(scorer) => {
+                    Placer::spiral_place(self, rs, &mut plate.clone(), &mut part, scorer)
+                }
+                None => SPIRAL_SCORERS.iter().find_map(|scorer| {
+                    Placer::spiral_place(self, rs, &mut plate.clone(), &mut part, *scorer)
                 }),
-            };
+            },
+        };
 
         if let Some((better_x, better_y, better_r)) = res {
             part.set_rotation(better_r as i32);
@@ -78,21 +80,75 @@ impl<'a> Placer<'a> {
         }
     }
 
+    // scorer_name names the scorer tried first when placing parts.
+    pub(crate) fn scorer_name(&self) -> String {
+        let zone = zone_scorer(self.request);
+        match configured_scorer(self.request, zone.as_ref()) {
+            Some(scorer) => scorer.name(),
+            None => match self.request.algorithm.strategy {
+                Strategy::PixelPack => GravityScorer::new(self.x_coef, self.y_coef).name(),
+                Strategy::SpiralPlace => SPIRAL_SCORERS[0].name(),
+            },
+        }
+    }
+
+    // Builds the candidate for the part at its current offset and rotation, along with
+    // the bounding box of the placed parts once the candidate is included.
+    fn make_candidate<'c, 'b>(
+        &self,
+        plate: &'c Plate<'b>,
+        part: &'c PlacedPart<'b>,
+        rotation: usize,
+        position: Position,
+    ) -> (Candidate<'c, 'b>, Rect) {
+        let (x, y) = (part.get_x(), part.get_y());
+        let bmp = part.get_bitmap();
+
+        let cur = Rect {
+            width: bmp.width as f64,
+            height: bmp.height as f64,
+            center_x: bmp.center_x + x / self.request.precision,
+            center_y: bmp.center_y + y / self.request.precision,
+        };
+
+        let merged = if let Some(r) = &self.current_bounding_box {
+            r.combine(&cur)
+        } else {
+            cur
+        };
+
+        let candidate = Candidate {
+            part,
+            plate,
+            x,
+            y,
+            rotation,
+            position,
+            bounding_width: merged.width,
+            bounding_height: merged.height,
+            gravity: self.gravity_targets(),
+        };
+
+        (candidate, merged)
+    }
+
     fn pixel_place<'b>(
         &mut self,
         rs: usize,
         plate: &mut Plate<'b>,
         part: &mut PlacedPart<'b>,
+        scorer: &dyn PlacementScorer,
     ) -> Option<(f64, f64, usize)> {
         let mut better_x = 0.0;
         let mut better_y = 0.0;
-        let mut better_score = 0.0;
+        let mut better_score = Score::worst();
+        let mut better_box = None;
         let mut better_r = 0;
         let mut found = false;
 
         // Conditionally reverse iteration direction
         let make_rot_iter = || {
-            if self.rotate_direction != 0 {
+            if self.rotate_direction == RotateDirection::Clockwise {
                 itertools::Either::Left((0..rs).rev())
             } else {
                 itertools::Either::Right(0..rs)
@@ -112,30 +168,32 @@ impl<'a> Placer<'a> {
                 .flatten()
                 .map(|(x, y)| {
                     (
-                        x + self.request.center_x - plate.width / 2.0,
-                        y + self.request.center_y - plate.height / 2.0,
+                        x + plate.center_x - plate.width / 2.0,
+                        y + plate.center_y - plate.height / 2.0,
                     )
                 })
         };
 
-        for (x, y) in make_iter() {
+        for (i, (x, y)) in make_iter().enumerate() {
+            if i % STOP_CHECK_INTERVAL == 0 && self.should_stop() {
+                return None;
+            }
+
             part.set_offset(x, y);
             for r in make_rot_iter() {
                 let vr = (r + self.rotate_offset as usize) % rs;
                 part.set_rotation(vr as i32);
-                let score = {
-                    let gx = part.get_gx() + x;
-                    let gy = part.get_gy() + y;
-                    gy * self.y_coef + gx * self.x_coef
-                };
+                let (candidate, cur_rect) = self.make_candidate(plate, part, vr, Inside);
+                let score = scorer.score(&candidate);
 
-                if !found || score < better_score {
+                if !found || scorer.prefers(score, better_score) {
                     if plate.can_place(&part) {
                         found = true;
                         better_x = x;
                         better_y = y;
                         better_r = vr;
                         better_score = score;
+                        better_box = Some(cur_rect);
                         // break 'outer;
                     }
                 }
@@ -145,103 +203,87 @@ impl<'a> Placer<'a> {
         if !found {
             None
         } else {
+            self.current_bounding_box = better_box;
             Some((better_x, better_y, better_r))
         }
     }
 
-    fn spiral_place<'b, T: ScoreWrapper>(
+    // spiral_origin is where the spiral starts, relative to the plate's bottom left
+    // corner. It is the gravity target, but the original gravity starts from the far
+    // corner of the original plate instead.
+    pub(super) fn spiral_origin(&self, plate: &Plate) -> (f64, f64) {
+        if self.gravity == Gravity::Original {
+            let shape = &self.request.plate_shape;
+            return if shape.width() < plate.width {
+                (shape.width(), shape.height())
+            } else {
+                (plate.width, plate.height)
+            };
+        }
+
+        let (target_x, target_y) = self.gravity_targets();
+        (
+            target_x.value() - (plate.center_x - plate.width / 2.0),
+            target_y.value() - (plate.center_y - plate.height / 2.0),
+        )
+    }
+
+    fn spiral_place<'b>(
         &mut self,
         rs: usize,
         plate: &mut Plate<'b>,
         part: &mut PlacedPart<'b>,
+        scorer: &dyn PlacementScorer,
     ) -> Option<(f64, f64, usize)> {
         let mut better_x = 0.0;
         let mut better_y = 0.0;
         let mut better_r = 0;
         let mut found = false;
 
-        let mut better_score = T::from(Score {
-            position: Outside,
-            moment_of_inertial: FloatWrapper(f64::INFINITY),
-            x_pos: FloatWrapper(f64::INFINITY),
-            y_pos: FloatWrapper(f64::INFINITY),
-        });
+        let mut better_score = Score::worst();
+        let mut better_box = None;
 
         // Conditionally reverse iteration direction
         let make_rot_iter = || {
-            if self.rotate_direction != 0 {
+            if self.rotate_direction == RotateDirection::Clockwise {
                 itertools::Either::Left((0..rs).rev())
             } else {
                 itertools::Either::Right(0..rs)
             }
         };
 
-        let initial_box = self.current_bounding_box.clone();
-
-        let spiral = spiral_iterator(
-            self.request.delta,
-            plate.width,
-            plate.height,
-            self.request.plate_shape.width(),
-            self.request.plate_shape.height(),
-        )
-        .map(|(x, y)| {
-            (
-                x + plate.center_x - plate.width / 2.0,
-                y + plate.center_y - plate.height / 2.0,
-            )
-        });
+        let (plate_x, plate_y) = (
+            plate.center_x - plate.width / 2.0,
+            plate.center_y - plate.height / 2.0,
+        );
+        let origin = self.spiral_origin(plate);
+        let spiral = spiral_iterator(self.request.delta, plate.width, plate.height, origin)
+            .map(|(x, y)| (x + plate_x, y + plate_y));
 
         let cond = self.request.plate_shape.width() + (plate.center_x - plate.width / 2.0);
 
-        for (x, y) in spiral {
+        for (i, (x, y)) in spiral.enumerate() {
+            if i % STOP_CHECK_INTERVAL == 0 && self.should_stop() {
+                return None;
+            }
+
             part.set_offset(x, y);
             for r in make_rot_iter() {
                 let vr = (r + self.rotate_offset as usize) % rs;
                 part.set_rotation(vr as i32);
-                let cur_rect;
-                let bmp = part.get_bitmap();
-
-                let score = {
-                    let w2 = bmp.width;
-                    let h2 = bmp.height;
-                    let (c2_x, c2_y) = (bmp.center_x, bmp.center_y);
-
-                    let cur = Rect {
-                        width: w2 as f64,
-                        height: h2 as f64,
-                        center_x: c2_x + x / self.request.precision,
-                        center_y: c2_y + y / self.request.precision,
-                    };
-
-                    let merged = if let Some(r) = &initial_box {
-                        r.combine(&cur)
-                    } else {
-                        cur.clone()
-                    };
-
-                    let position = if x > cond { Outside } else { Inside };
-
-                    let moment_of_inertia =
-                        f64::powf(merged.height, 2.0) + f64::powf(merged.width, 2.0);
-                    cur_rect = Some(merged);
-
-                    T::from(Score {
-                        position,
-                        moment_of_inertial: FloatWrapper(moment_of_inertia),
-                        x_pos: FloatWrapper(x),
-                        y_pos: FloatWrapper(y),
-                    })
-                };
-
-                if !found || better_score.compare_prefer(score) == Second {
+
+                let position = if x > cond { Outside } else { Inside };
+                let (candidate, cur_rect) = self.make_candidate(plate, part, vr, position);
+                let score = scorer.score(&candidate);
+
+                if !found || scorer.prefers(score, better_score) {
                     if plate.can_place(&part) {
                         found = true;
                         better_x = x;
                         better_y = y;
                         better_r = vr;
                         better_score = score;
-                        self.current_bounding_box = cur_rect;
+                        better_box = Some(cur_rect);
                     }
                 }
             }
@@ -250,7 +292,32 @@ impl<'a> Placer<'a> {
         if !found {
             None
         } else {
+            self.current_bounding_box = better_box;
             Some((better_x, better_y, better_r))
         }
     }
 }
+
+fn zone_scorer(request: &Request) -> Option<ZoneScorer<'_>> {
+    request.quality_map.as_ref().map(|map| {
+        ZoneScorer::new(
+            map,
+            (request.center_x, request.center_y),
+            (request.plate_shape.width(), request.plate_shape.height()),
+            request.precision,
+        )
+    })
+}
+
+// The scorer that replaces the strategy's built-in scoring, if any.
+fn configured_scorer<'s>(
+    request: &'s Request,
+    zone: Option<&'s ZoneScorer<'s>>,
+) -> Option<&'s dyn PlacementScorer> {
+    match (&request.scorer, request.layout_mode, zone) {
+        (Some(scorer), _, _) => Some(scorer.as_ref()),
+        (None, LayoutMode::Spread, _) => Some(&SpreadScorer),
+        (None, LayoutMode::Compact, Some(zone)) => Some(zone),
+        (None, LayoutMode::Compact, None) => None,
+    }
+}
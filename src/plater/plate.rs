@@ -1,9 +1,13 @@
+use std::cell::OnceCell;
+use std::cmp::Ordering::{Equal, Greater, Less};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::plater::bitmap::Bitmap;
+use crate::plater::bitmap::{Bitmap, DistanceMap};
 use crate::plater::placed_part::PlacedPart;
 use crate::plater::placement::Placement;
 use crate::plater::plate_shape::PlateShape;
+use crate::plater::request::Anchor;
+use crate::plater::util;
 
 static COUNTER: AtomicUsize = AtomicUsize::new(1);
 
@@ -21,39 +25,153 @@ pub struct Plate<'a> {
     bitmap: Bitmap,
     pub(crate) center_x: f64,
     pub(crate) center_y: f64,
+    // shape is kept to rebuild the plate bitmap when parts move
+    shape: Box<dyn PlateShape>,
+    // anchored records whether the layout was moved to the anchor point
+    anchored: bool,
+    // clearance_map is computed on demand and reset whenever a part is placed
+    clearance_map: OnceCell<DistanceMap>,
 }
 
 impl<'a> Plate<'a> {
-    pub(crate) fn align(&mut self, original_width: f64, original_height: f64) {
-        let bound = self.bitmap.get_bound();
-        let (bottom_space, top_space, left_space, right_space) = bound;
+    // anchor moves the parts that are not locked or pinned, as a group, to the anchor
+    // point of the plate. expanded gives the axes the plate was expanded along. The
+    // parts are left where they are if they would collide with the fixed parts or leave
+    // the plate, and is_anchored reports that.
+    pub(crate) fn anchor(&mut self, anchor: Anchor, expanded: (bool, bool)) {
+        if anchor == Anchor::None {
+            return;
+        }
 
-        for part in &mut self.parts {
-            let (x, y) = (part.get_x(), part.get_y());
-            part.set_offset(x - left_space, y);
+        let (dx, dy) = self.anchor_offset(anchor, expanded).unwrap_or((0, 0));
+        if dx == 0 && dy == 0 {
+            self.anchored = true;
+            return;
+        }
+
+        let mut bitmap = self.shape.make_masked_bitmap(self.precision);
+        let (left, bottom) = self.bottom_left();
+        let offset = |part: &PlacedPart| {
+            (
+                ((part.get_x() - left) / self.precision) as i32,
+                ((part.get_y() - bottom) / self.precision) as i32,
+            )
+        };
+
+        for part in self.parts.iter().filter(|part| part.is_fixed()) {
+            let (x, y) = offset(part);
+            bitmap.write(part.get_bitmap(), x, y);
+        }
+
+        let mut parts = self.parts.clone();
+        for part in parts.iter_mut().filter(|part| !part.is_fixed()) {
+            let (x, y) = offset(part);
+            let (x, y) = (x + dx, y + dy);
+            let bmp = part.get_bitmap();
+            if x < 0 || y < 0 || x + bmp.width > bitmap.width || y + bmp.height > bitmap.height {
+                return;
+            }
+            if bmp.overlaps(&bitmap, x, y) {
+                return;
+            }
+
+            bitmap.write(bmp, x, y);
+            part.set_offset(
+                part.get_x() + dx as f64 * self.precision,
+                part.get_y() + dy as f64 * self.precision,
+            );
         }
+
+        self.parts = parts;
+        self.bitmap = bitmap;
+        self.clearance_map.take();
+        self.anchored = true;
     }
 
-    pub(crate) fn center(&mut self) {
-        let (bottom_space, top_space, left_space, right_space) = self.bitmap.get_bound();
+    // anchor_offset returns the offset in pixels that moves the bounding box of the
+    // movable parts to the anchor point, if there are any parts to move.
+    fn anchor_offset(&self, anchor: Anchor, expanded: (bool, bool)) -> Option<(i32, i32)> {
+        let (left, bottom) = self.bottom_left();
+        let (min_x, min_y, max_x, max_y) = self
+            .parts
+            .iter()
+            .filter(|part| !part.is_fixed())
+            .map(|part| {
+                let bmp = part.get_bitmap();
+                let x = ((part.get_x() - left) / self.precision) as i32;
+                let y = ((part.get_y() - bottom) / self.precision) as i32;
+                (x, y, x + bmp.width, y + bmp.height)
+            })
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))?;
+
+        // Less, Equal and Greater anchor to the low end, middle and high end of an axis,
+        // and None leaves the axis as it is
+        let (x_end, y_end) = match anchor {
+            Anchor::None => return None,
+            Anchor::Auto if expanded == (false, false) => (Some(Equal), Some(Equal)),
+            Anchor::Auto => (expanded.0.then_some(Less), expanded.1.then_some(Less)),
+            Anchor::Center => (Some(Equal), Some(Equal)),
+            Anchor::BottomLeft => (Some(Less), Some(Less)),
+            Anchor::BottomRight => (Some(Greater), Some(Less)),
+            Anchor::TopLeft => (Some(Less), Some(Greater)),
+            Anchor::TopRight => (Some(Greater), Some(Greater)),
+            Anchor::Bottom => (Some(Equal), Some(Less)),
+            Anchor::Top => (Some(Equal), Some(Greater)),
+            Anchor::Left => (Some(Less), Some(Equal)),
+            Anchor::Right => (Some(Greater), Some(Equal)),
+        };
+        let shift = |end: Option<std::cmp::Ordering>, min: i32, max: i32, size: i32| match end {
+            None => 0,
+            Some(Less) => -min,
+            Some(Equal) => (size - (max - min)) / 2 - min,
+            Some(Greater) => size - max,
+        };
+
         let (width, height) = self.bitmap.get_dims();
+        Some((
+            shift(x_end, min_x, max_x, width),
+            shift(y_end, min_y, max_y, height),
+        ))
+    }
 
-        let new_ref = (left_space, bottom_space);
-        let (new_width, new_height) = (
-            width as f64 - left_space - right_space,
-            height as f64 - bottom_space - top_space,
-        );
-        let new_center = (new_ref.0 + new_width / 2.0, new_ref.1 + new_height / 2.0);
+    // parts_bounding_box_area is the area of the bounding box of the parts on the plate.
+    pub(crate) fn parts_bounding_box_area(&self) -> f64 {
+        self.parts
+            .iter()
+            .map(|part| {
+                let bmp = part.get_bitmap();
+                let (x, y) = (part.get_x(), part.get_y());
+                let (width, height) = (bmp.width as f64, bmp.height as f64);
+                (x, y, x + width * self.precision, y + height * self.precision)
+            })
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+            .map_or(0.0, |(min_x, min_y, max_x, max_y)| {
+                (max_x - min_x) * (max_y - min_y)
+            })
+    }
 
-        let (tr_x, tr_y) = (
-            (width as f64) / 2.0 - new_center.0,
-            (height as f64) / 2.0 - new_center.1,
-        );
+    // parts_hull_area is the area of the convex hull of the parts on the plate.
+    pub(crate) fn parts_hull_area(&self) -> f64 {
+        let points = self
+            .parts
+            .iter()
+            .flat_map(|part| {
+                let (x, y) = (part.get_x(), part.get_y());
+                part.get_bitmap()
+                    .hull_points()
+                    .into_iter()
+                    .map(move |(px, py)| (x + px * self.precision, y + py * self.precision))
+            })
+            .collect();
+
+        util::polygon_area(&util::convex_hull(points))
+    }
 
-        for part in &mut self.parts {
-            let (x, y) = (part.get_x(), part.get_y());
-            part.set_offset(x + tr_x, y + tr_y);
-        }
+    fn bottom_left(&self) -> (f64, f64) {
+        (
+            self.center_x - self.width / 2.0,
+            self.center_y - self.height / 2.0,
+        )
     }
 
     pub(crate) fn new(
@@ -75,6 +193,9 @@ impl<'a> Plate<'a> {
             bitmap,
             center_x,
             center_y,
+            shape: shape.dyn_clone(),
+            anchored: false,
+            clearance_map: OnceCell::new(),
         }
     }
 
@@ -127,6 +248,7 @@ impl<'a> Plate<'a> {
         let off_x = (placed_part.get_x() - (self.center_x - self.width / 2.0)) / self.precision;
         let off_y = (placed_part.get_y() - (self.center_y - self.height / 2.0)) / self.precision;
         self.bitmap.write(bitmap, off_x as i32, off_y as i32);
+        self.clearance_map.take();
 
         self.parts.push(placed_part);
     }
@@ -166,6 +288,21 @@ impl<'a> Plate<'a> {
         )
     }
 
+    // clearance returns the distance in pixels between the part, at its current offset,
+    // and the nearest placed part or plate edge.
+    pub(crate) fn clearance(&self, placed_part: &PlacedPart) -> f64 {
+        let map = self.clearance_map.get_or_init(|| self.bitmap.distance_map());
+
+        let x = placed_part.get_x() - (self.center_x - self.width / 2.0);
+        let y = placed_part.get_y() - (self.center_y - self.height / 2.0);
+
+        map.clearance(
+            placed_part.get_outline(),
+            (x / self.precision) as i32,
+            (y / self.precision) as i32,
+        )
+    }
+
     pub(crate) fn count_parts(&self) -> usize {
         (&self.parts).len()
     }
@@ -179,6 +316,30 @@ impl<'a> Plate<'a> {
         result
     }
 
+    // get_print_order returns the IDs of the parts in the order to print them one at a
+    // time: shortest first, so parts too tall for the gantry are finished last, and
+    // from front to back among parts of the same height.
+    pub fn get_print_order(&self) -> Vec<String> {
+        let mut parts = self.parts.iter().collect::<Vec<_>>();
+        parts.sort_by(|a, b| {
+            f64::total_cmp(&a.part.model_height, &b.part.model_height)
+                .then_with(|| f64::total_cmp(&a.get_center_y(), &b.get_center_y()))
+                .then_with(|| f64::total_cmp(&a.get_center_x(), &b.get_center_x()))
+        });
+
+        parts
+            .iter()
+            .map(|part| part.get_id().to_string())
+            .collect()
+    }
+
+    // is_anchored returns whether the layout was moved to the anchor point of the
+    // request. It is not when moving it would collide with locked or pinned parts, or
+    // when the anchor is Anchor::None.
+    pub fn is_anchored(&self) -> bool {
+        self.anchored
+    }
+
     pub fn get_ppm(&self) -> String {
         self.bitmap.to_ppm()
     }
@@ -186,4 +347,113 @@ impl<'a> Plate<'a> {
     pub fn get_size(&self) -> (f64, f64) {
         (self.width, self.height)
     }
+
+    pub fn get_center(&self) -> (f64, f64) {
+        (self.center_x, self.center_y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::part::Part;
+    use crate::plater::placed_part::PlacedPart;
+    use crate::plater::plate::Plate;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::request::{
+        Algorithm, Anchor, BedExpansionMode, ConfigOrder, PointEnumerationMode, Request, Strategy,
+    };
+
+    // request_and_part returns a request for a 60 by 60 mm plate, and a solid part of
+    // 20 by 20 pixels for it.
+    fn request_and_part() -> (Request, Part) {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(60.0, 60.0, 1.0);
+        let request = Request::new(shape, 1.0, algorithm, 30.0, 30.0);
+        let bitmap = Bitmap::new_bitmap_with_data(20, 20, &[1; 400]).unwrap();
+        let part = Part::new(
+            String::from("p0"),
+            bitmap,
+            0.0,
+            0.0,
+            request.precision,
+            request.delta_r,
+            request.spacing,
+            60.0,
+            60.0,
+            false,
+        )
+        .unwrap();
+        (request, part)
+    }
+
+    // anchored_at returns the bottom left corner of the part, in mm, once the plate
+    // holding it at (20, 20) is anchored.
+    fn anchored_at(anchor: Anchor, expanded: (bool, bool)) -> (f64, f64) {
+        let (request, part) = request_and_part();
+        let mut placed = PlacedPart::new_placed_part(&part);
+        placed.set_offset(20.0, 20.0);
+
+        let mut plate = Plate::new(request.plate_shape.as_ref(), request.precision, 30.0, 30.0);
+        plate.place(placed);
+        plate.anchor(anchor, expanded);
+        assert!(plate.is_anchored());
+
+        (plate.parts[0].get_x(), plate.parts[0].get_y())
+    }
+
+    #[test]
+    fn anchors_move_the_layout_to_corners_and_edges() {
+        let (request, part) = request_and_part();
+        let size = part.get_bitmap(0).width as f64 * request.precision;
+        let (low, mid, high) = (0.0, (60.0 - size) / 2.0, 60.0 - size);
+
+        let cases = [
+            (Anchor::Center, (mid, mid)),
+            (Anchor::BottomLeft, (low, low)),
+            (Anchor::BottomRight, (high, low)),
+            (Anchor::TopLeft, (low, high)),
+            (Anchor::TopRight, (high, high)),
+            (Anchor::Bottom, (mid, low)),
+            (Anchor::Top, (mid, high)),
+            (Anchor::Left, (low, mid)),
+            (Anchor::Right, (high, mid)),
+        ];
+        for (anchor, expected) in cases {
+            assert_eq!(anchored_at(anchor, (false, false)), expected, "{anchor:?}");
+        }
+    }
+
+    #[test]
+    fn auto_anchor_only_aligns_expanded_axes() {
+        let (request, part) = request_and_part();
+        let size = part.get_bitmap(0).width as f64 * request.precision;
+        let mid = (60.0 - size) / 2.0;
+
+        assert_eq!(anchored_at(Anchor::Auto, (false, false)), (mid, mid));
+        assert_eq!(anchored_at(Anchor::Auto, (true, false)), (0.0, 20.0));
+        assert_eq!(anchored_at(Anchor::Auto, (false, true)), (20.0, 0.0));
+    }
+
+    #[test]
+    fn anchor_leaves_layout_that_would_hit_fixed_parts() {
+        let (request, part) = request_and_part();
+        let mut pinned = PlacedPart::new_placed_part(&part);
+        pinned.pinned_plate = Some(0);
+        let mut placed = PlacedPart::new_placed_part(&part);
+        placed.set_offset(20.0, 20.0);
+
+        let mut plate = Plate::new(request.plate_shape.as_ref(), request.precision, 30.0, 30.0);
+        plate.place(pinned);
+        plate.place(placed);
+        plate.anchor(Anchor::BottomLeft, (false, false));
+
+        assert!(!plate.is_anchored());
+        assert_eq!((plate.parts[1].get_x(), plate.parts[1].get_y()), (20.0, 20.0));
+    }
 }
@@ -8,6 +8,9 @@ pub trait PlateShape: Send + Sync {
     fn string(&self) -> String;
     fn make_masked_bitmap(&self, precision: f64) -> Bitmap;
     fn extend_right(&self, size: f64) -> Box<dyn PlateShape>;
+    fn extend_up(&self, size: f64) -> Box<dyn PlateShape>;
+    // extend grows the plate by the same factor along both axes, keeping its aspect
+    fn extend(&self, size: f64) -> Box<dyn PlateShape>;
     fn dyn_clone(&self) -> Box<dyn PlateShape>;
     fn contract(&self, size: f64) -> Option<Box<dyn PlateShape>>;
 }
@@ -97,6 +100,22 @@ impl PlateShape for PlateRectangle {
         })
     }
 
+    fn extend_up(&self, size: f64) -> Box<dyn PlateShape> {
+        Box::new(PlateRectangle {
+            resolution: self.resolution,
+            width: self.width,
+            height: self.height * size,
+        })
+    }
+
+    fn extend(&self, size: f64) -> Box<dyn PlateShape> {
+        Box::new(PlateRectangle {
+            resolution: self.resolution,
+            width: self.width * size,
+            height: self.height * size,
+        })
+    }
+
     fn dyn_clone(&self) -> Box<dyn PlateShape> {
         Box::new(PlateRectangle {
             resolution: self.resolution,
@@ -132,6 +151,8 @@ pub struct PlateCircle {
     resolution: f64,
     diameter: f64,
     plate_expansion_factor: f64,
+    // vertical_expansion_factor is plate_expansion_factor for the height of the plate
+    vertical_expansion_factor: f64,
 }
 
 impl PlateCircle {
@@ -140,6 +161,7 @@ impl PlateCircle {
             resolution,
             diameter: diameter * resolution,
             plate_expansion_factor,
+            vertical_expansion_factor: 1.0,
         }
     }
 }
@@ -148,7 +170,7 @@ fn make_standard_circle_bitmap(shape: &PlateCircle, precision: f64) -> Bitmap {
     let width = shape.width();
     let height = shape.height();
 
-    let mut bitmap = Bitmap::new((width * shape.plate_expansion_factor / precision) as i32, (height / precision) as i32);
+    let mut bitmap = Bitmap::new((width / precision) as i32, (height / precision) as i32);
     // fill all pixels outside plate radius so parts cannot be placed there
     let radius = shape.diameter / 2.0;
 
@@ -175,7 +197,7 @@ impl PlateShape for PlateCircle {
     }
 
     fn height(&self) -> f64 {
-        self.diameter
+        self.diameter * self.vertical_expansion_factor
     }
 
     fn string(&self) -> String {
@@ -183,17 +205,16 @@ impl PlateShape for PlateCircle {
     }
 
     fn make_masked_bitmap(&self, precision: f64) -> Bitmap {
-        if self.plate_expansion_factor <= 1.0 {
+        if self.plate_expansion_factor <= 1.0 && self.vertical_expansion_factor <= 1.0 {
             return make_standard_circle_bitmap(self, precision);
         }
 
-        let regular = make_standard_circle_bitmap(&PlateCircle { diameter: self.diameter, resolution: self.resolution, plate_expansion_factor: 1.0 }, precision);
+        let regular = make_standard_circle_bitmap(&PlateCircle::new(self.diameter / self.resolution, self.resolution, 1.0), precision);
 
         let width = self.width();
         let height = self.height();
 
-        let mut bitmap = Bitmap::new((width * self.plate_expansion_factor / precision) as i32, (height / precision) as i32);
-
+        let mut bitmap = Bitmap::new((width / precision) as i32, (height / precision) as i32);
 
         // Super-impose the normal-sized circle onto the expanded bitmap
         for y in 0..regular.height {
@@ -208,33 +229,42 @@ impl PlateShape for PlateCircle {
     // We return a rectangle when expanding a circle
     fn extend_right(&self, size: f64) -> Box<dyn PlateShape> {
         Box::new(PlateCircle {
-            resolution: self.resolution,
-            diameter: self.diameter,
             plate_expansion_factor: self.plate_expansion_factor * size,
+            ..self.clone()
         })
     }
 
-    fn dyn_clone(&self) -> Box<dyn PlateShape> {
+    fn extend_up(&self, size: f64) -> Box<dyn PlateShape> {
         Box::new(PlateCircle {
-            resolution: self.resolution,
-            diameter: self.diameter,
-            plate_expansion_factor: self.plate_expansion_factor,
+            vertical_expansion_factor: self.vertical_expansion_factor * size,
+            ..self.clone()
+        })
+    }
+
+    fn extend(&self, size: f64) -> Box<dyn PlateShape> {
+        Box::new(PlateCircle {
+            plate_expansion_factor: self.plate_expansion_factor * size,
+            vertical_expansion_factor: self.vertical_expansion_factor * size,
+            ..self.clone()
         })
     }
 
+    fn dyn_clone(&self) -> Box<dyn PlateShape> {
+        Box::new(self.clone())
+    }
+
     // Also mask bitmap
     fn contract(&self, size: f64) -> Option<Box<dyn PlateShape>> {
         if size <= 0.0 {
             return None;
         }
 
-        let width = self.width() - size * self.resolution;
+        let diameter = self.diameter - size * self.resolution;
 
-        if width <= 0.0 {
+        if diameter <= 0.0 {
             return None;
         }
 
-        let circle = PlateCircle::new(width / self.resolution, self.resolution, self.plate_expansion_factor);
-        Some(Box::new(circle))
+        Some(Box::new(PlateCircle { diameter, ..self.clone() }))
     }
 }
@@ -5,7 +5,7 @@ pub struct Point {
 }
 
 impl Point {
-    pub(crate) fn new(x: f64, y: f64) -> Self {
+    pub fn new(x: f64, y: f64) -> Self {
         Point { x, y }
     }
 
@@ -12,7 +12,15 @@ pub enum ProgressMessage<'request> {
     StringMessage(String),
     SolutionFound {
         placer_index: u32,
-        solution: Solution<'request>,
+        solution: Box<Solution<'request>>,
+    },
+    // PlacerProgress reports how far a placer is through its parts. search_index is
+    // the plate size being tried, in single plate mode.
+    PlacerProgress {
+        placer_index: u32,
+        search_index: Option<usize>,
+        parts_placed: usize,
+        total_parts: usize,
     },
 }
 
@@ -30,4 +38,9 @@ impl<'request, F2: Fn(ProgressMessage)> ProgressMessenger<F2> {
     pub fn send_message<F: Fn() -> ProgressMessage<'request>>(&self, sender_function: F) {
         (self.receiver_function)(sender_function());
     }
+
+    // forward passes on a message that was built elsewhere, such as on another thread.
+    pub(crate) fn forward(&self, message: ProgressMessage<'request>) {
+        (self.receiver_function)(message);
+    }
 }
@@ -0,0 +1,54 @@
+use crate::plater::bitmap::Bitmap;
+
+// QualityMap describes how good each area of the plate is for printing,
+// from 0 (worst) to 1 (best).
+pub enum QualityMap {
+    // Bitmap samples the quality (0 to 255) from a bitmap stretched over the plate.
+    Bitmap(Bitmap),
+    // Radial maps the distance from the plate centre to a quality. The distance is
+    // normalised so that it is 0 at the centre and 1 at the middle of each plate edge.
+    Radial(Box<dyn Fn(f64) -> f64 + Send + Sync>),
+}
+
+impl QualityMap {
+    // quality_at returns the quality at a point given relative to the plate centre,
+    // where (-1, -1) is the bottom left corner and (1, 1) the top right corner.
+    pub(crate) fn quality_at(&self, u: f64, v: f64) -> f64 {
+        let quality = match self {
+            QualityMap::Bitmap(bitmap) => {
+                let (width, height) = bitmap.get_dims();
+                let x = ((u + 1.0) / 2.0 * width as f64) as i32;
+                let y = ((v + 1.0) / 2.0 * height as f64) as i32;
+                let x = x.clamp(0, width - 1);
+                let y = y.clamp(0, height - 1);
+                bitmap.get_point(x, y) as f64 / 255.0
+            }
+            QualityMap::Radial(f) => f(f64::sqrt(u * u + v * v)),
+        };
+
+        quality.clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::quality_map::QualityMap;
+
+    #[test]
+    fn quality_at_samples_and_clamps() {
+        let radial = QualityMap::Radial(Box::new(|distance| 1.0 - distance));
+        assert_eq!(radial.quality_at(0.0, 0.0), 1.0);
+        assert_eq!(radial.quality_at(1.0, 0.0), 0.0);
+        assert_eq!(radial.quality_at(1.0, 1.0), 0.0);
+
+        let bitmap = Bitmap::new_bitmap_with_data(2, 2, &[0, 51, 102, 255]).unwrap();
+        let map = QualityMap::Bitmap(bitmap);
+        assert_eq!(map.quality_at(-1.0, -1.0), 0.0);
+        assert_eq!(map.quality_at(0.5, -0.5), 0.2);
+        assert_eq!(map.quality_at(-0.5, 0.5), 0.4);
+        // Points on or past the top right edge sample the last pixel
+        assert_eq!(map.quality_at(1.0, 1.0), 1.0);
+        assert_eq!(map.quality_at(3.0, 2.0), 1.0);
+    }
+}
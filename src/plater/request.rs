@@ -1,14 +1,22 @@
 use std::collections::HashMap;
 use std::f64::consts::PI;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 use rand::prelude::SliceRandom;
 use rand::Rng;
+use rayon::prelude::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
 use thiserror::Error;
 
+use crate::plater::cancellation::CancellationToken;
 use crate::plater::part::Part;
-use crate::plater::placer::{Placer, SortMode};
+use crate::plater::placement::Placement;
+use crate::plater::placer::score::PlacementScorer;
+use crate::plater::placer::{Gravity, Placer, RotateDirection, SortMode};
 use crate::plater::plate_shape::{PlateShape, Shape};
+use crate::plater::quality_map::QualityMap;
+use crate::plater::solution::Solution;
 use crate::stl;
 
 // DEFAULT_RESOLUTION is the default bitmap resolution, in pixels per mm.
@@ -21,9 +29,16 @@ pub struct Request {
     pub(crate) single_plate_mode: bool,
     // sort_modes is a list of sort modes to attempt when placing.
     pub(crate) sort_modes: Vec<SortMode>,
-    // max_threads is the maximum number of goroutines to use when placing.
-    // Set this to 0 or a negative value for no limit.
+    // custom_order lists the part IDs placed first by SortMode::Custom
+    pub(crate) custom_order: Vec<String>,
+    // gravities and rotate_directions are attempted in every combination with the sort modes.
+    pub(crate) gravities: Vec<Gravity>,
+    pub(crate) rotate_directions: Vec<RotateDirection>,
+    // max_threads is the maximum number of threads to use when placing.
+    // Set this to 0 to use every core.
     pub(crate) max_threads: usize,
+    // thread_pool runs all parallel work for the request, it is built on first use
+    pub(crate) thread_pool: OnceLock<ThreadPool>,
     pub(crate) precision: f64,
     // precision
     pub(crate) spacing: f64, // part spacing
@@ -34,7 +49,7 @@ pub struct Request {
 
     // Parts to place (TODO: revise, can this become vec)
     pub(crate) parts: HashMap<String, Part>,
-    resolution: f64,
+    pub(crate) resolution: f64,
     // internal resolution (pixels per mm)
     pub(crate) algorithm: Algorithm,
 
@@ -42,6 +57,26 @@ pub struct Request {
     pub(crate) center_y: f64,
 
     pub(crate) timeout: Option<Duration>,
+
+    // scorer overrides the built-in placement scoring when set.
+    pub(crate) scorer: Option<Box<dyn PlacementScorer>>,
+    pub(crate) layout_mode: LayoutMode,
+    // quality_map, if set, steers sensitive parts towards the better areas of the plate
+    pub(crate) quality_map: Option<QualityMap>,
+    // seed drives every random choice made while placing
+    pub(crate) seed: u64,
+    pub(crate) distribution: Distribution,
+    // plate_count is the number of plates to balance parts over, if fixed
+    pub(crate) plate_count: Option<usize>,
+    // plate_limits holds the limits in resolution units
+    pub(crate) plate_limits: PlateLimits,
+    pub(crate) sequential: Option<SequentialPrinting>,
+    pub(crate) hole_nesting: HoleNesting,
+    pub(crate) anchor: Anchor,
+    pub(crate) search_policy: SearchPolicy,
+    pub(crate) expansion_direction: ExpansionDirection,
+    pub(crate) objective: Objective,
+    pub(crate) cancellation: CancellationToken,
 }
 
 #[derive(Clone)]
@@ -62,6 +97,260 @@ pub enum PointEnumerationMode {
     Spiral,
 }
 
+// LayoutMode selects the placement objective.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LayoutMode {
+    // Compact packs parts as tightly as possible.
+    Compact,
+    // Spread maximises the clearance between parts and from the plate edges.
+    Spread,
+}
+
+// Distribution selects how parts are shared out over the plates in multi plate mode.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Distribution {
+    // FirstFit places each part on the first plate it fits on.
+    FirstFit,
+    // Balanced evens out the load of the plates, so they take a similar time to print.
+    Balanced(LoadMetric),
+    // HeightGrouped puts parts of similar model height on the same plates, so short
+    // parts are not printed alongside tall ones. A part only joins a plate if the
+    // tallest part on it is at most max_height_ratio times as tall, which is at least 1.
+    HeightGrouped { max_height_ratio: f64 },
+}
+
+// LoadMetric measures the load a part puts on a plate.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LoadMetric {
+    // Footprint is the area of the plate the part covers.
+    Footprint,
+    // Volume is the volume of the part's model, as set by Part::set_volume.
+    Volume,
+}
+
+impl LoadMetric {
+    pub(crate) fn load(self, part: &Part) -> f64 {
+        match self {
+            LoadMetric::Footprint => part.get_footprint_area(),
+            LoadMetric::Volume => part.get_volume(),
+        }
+    }
+}
+
+// PlateLimits caps what may be put on a single plate. Volumes are in mm³ and
+// footprint areas in mm².
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlateLimits {
+    pub max_parts: Option<usize>,
+    pub max_volume: Option<f64>,
+    pub max_footprint: Option<f64>,
+}
+
+// SearchKind is how the plate size is searched for in exponential bed expansion mode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchKind {
+    // Linear tries each plate size in turn, from the smallest.
+    Linear,
+    // Exponential doubles the plate index until a solution is found, then bisects.
+    Exponential,
+    // GoldenSection splits the remaining range at the golden ratio, towards the
+    // smaller plates.
+    GoldenSection,
+}
+
+// SearchPolicy controls the search for the smallest plate that fits all parts in
+// single plate mode. Distances are in mm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchPolicy {
+    // contraction_step is how much narrower each smaller plate size is
+    pub contraction_step: f64,
+    // expansion_step is how much longer each larger plate size is, along the
+    // expansion direction
+    pub expansion_step: f64,
+    // max_expansion is the longest the plate may become, as a multiple of its length
+    pub max_expansion: f64,
+    // search_limit is the number of bisection steps after which any solution is taken
+    pub search_limit: Option<usize>,
+    pub kind: SearchKind,
+}
+
+impl SearchPolicy {
+    pub fn fast() -> Self {
+        SearchPolicy {
+            contraction_step: 10.0,
+            expansion_step: 20.0,
+            max_expansion: 3.0,
+            search_limit: Some(3),
+            kind: SearchKind::Exponential,
+        }
+    }
+
+    pub fn balanced() -> Self {
+        SearchPolicy {
+            contraction_step: 5.0,
+            expansion_step: 10.0,
+            max_expansion: 5.0,
+            search_limit: Some(5),
+            kind: SearchKind::Exponential,
+        }
+    }
+
+    pub fn thorough() -> Self {
+        SearchPolicy {
+            contraction_step: 2.0,
+            expansion_step: 5.0,
+            max_expansion: 5.0,
+            search_limit: None,
+            kind: SearchKind::GoldenSection,
+        }
+    }
+
+    // expansion_steps is the number of larger plate sizes for a plate of the given
+    // length, in resolution units.
+    pub(crate) fn expansion_steps(&self, length: f64, resolution: f64) -> usize {
+        let step = self.expansion_step * resolution;
+        if step <= 0.0 {
+            return 0;
+        }
+
+        f64::ceil((self.max_expansion - 1.0).max(0.0) * length / step) as usize
+    }
+
+    // expansion_factor is the factor the plate length grows by after the given number
+    // of expansion steps.
+    pub(crate) fn expansion_factor(&self, steps: usize, length: f64, resolution: f64) -> f64 {
+        let factor = 1.0 + steps as f64 * self.expansion_step * resolution / length;
+        f64::min(factor, self.max_expansion)
+    }
+}
+
+// Objective ranks the solutions found by the placers, to choose the one returned.
+// Lower costs rank better.
+#[derive(Clone)]
+pub enum Objective {
+    FewestPlates,
+    // SmallestPlate prefers the smallest last plate, the plate that is searched for
+    SmallestPlate,
+    // SmallestBoundingBox prefers the smallest bounding box of the placed parts
+    SmallestBoundingBox,
+    // SmallestConvexHull prefers the smallest convex hull of the placed parts
+    SmallestConvexHull,
+    // BestBalance prefers the least difference in load between plates
+    BestBalance,
+    LowestPrintHeight,
+    // Custom ranks by the cost the closure gives each solution
+    Custom(Arc<dyn Fn(&Solution) -> f64 + Send + Sync>),
+    // Lexicographic ranks by each objective in turn, using the later objectives to
+    // break ties between the earlier ones
+    Lexicographic(Vec<Objective>),
+}
+
+impl Objective {
+    // then ranks by this objective, and then by next on a tie.
+    pub fn then(self, next: Objective) -> Objective {
+        match self {
+            Objective::Lexicographic(mut objectives) => {
+                objectives.push(next);
+                Objective::Lexicographic(objectives)
+            }
+            objective => Objective::Lexicographic(vec![objective, next]),
+        }
+    }
+
+    pub(crate) fn compare<'p>(&self, a: &Solution<'p>, b: &Solution<'p>) -> std::cmp::Ordering {
+        let cost: fn(&Solution<'p>) -> f64 = match self {
+            Objective::FewestPlates => Solution::score,
+            Objective::SmallestPlate => Solution::plate_area,
+            Objective::SmallestBoundingBox => Solution::bounding_box_area,
+            Objective::SmallestConvexHull => Solution::hull_area,
+            Objective::BestBalance => Solution::get_imbalance,
+            Objective::LowestPrintHeight => Solution::print_height,
+            Objective::Custom(cost) => return f64::total_cmp(&cost(a), &cost(b)),
+            Objective::Lexicographic(objectives) => {
+                return objectives
+                    .iter()
+                    .map(|objective| objective.compare(a, b))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(std::cmp::Ordering::Equal);
+            }
+        };
+
+        f64::total_cmp(&cost(a), &cost(b))
+    }
+}
+
+impl Default for Objective {
+    // The default prefers the smallest plate, as the plate search does
+    fn default() -> Self {
+        Objective::SmallestPlate
+            .then(Objective::FewestPlates)
+            .then(Objective::BestBalance)
+            .then(Objective::LowestPrintHeight)
+    }
+}
+
+// Anchor is the point of the plate that the layout is moved to once placed. Parts
+// that are locked or pinned stay where they are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Anchor {
+    // None leaves the layout where it was placed.
+    None,
+    // Auto centres the layout on plates of the original size. On expanded plates the
+    // layout is moved to the low end of the axes the plate grew along, so it starts at
+    // the original plate.
+    Auto,
+    Center,
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
+    // Bottom, Top, Left and Right anchor the layout to the middle of an edge.
+    Bottom,
+    Top,
+    Left,
+    Right,
+}
+
+// HoleNesting decides whether parts may be placed inside the holes of other parts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HoleNesting {
+    Allow,
+    Forbid,
+    // MinHoleArea only allows nesting in holes of at least the given area, in mm².
+    MinHoleArea(f64),
+}
+
+// SequentialPrinting describes a printer that finishes each part before starting the
+// next. Distances are in mm.
+#[derive(Clone, Copy, Debug)]
+pub struct SequentialPrinting {
+    // carriage_extents outline the extruder carriage around the nozzle, as the
+    // distances it reaches to the left, right, front and back
+    pub carriage_extents: (f64, f64, f64, f64),
+    // gantry_height is the height below the gantry and carriage, parts taller than it
+    // need the carriage clearance around them
+    pub gantry_height: f64,
+}
+
+// ExpansionDirection is the way the plate grows when the parts do not fit on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpansionDirection {
+    Right,
+    Up,
+    // Proportional grows both axes, keeping the aspect ratio of the plate
+    Proportional,
+}
+
+impl ExpansionDirection {
+    // length is the side of the plate that expansion steps are measured along
+    pub(crate) fn length(self, shape: &dyn PlateShape) -> f64 {
+        match self {
+            ExpansionDirection::Up => shape.height(),
+            ExpansionDirection::Right | ExpansionDirection::Proportional => shape.width(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum BedExpansionMode {
     Linear,
@@ -72,6 +361,16 @@ pub enum BedExpansionMode {
 pub enum PlacingError {
     #[error("No solutions found")]
     NoSolutionFound,
+    #[error("Unknown part {0}")]
+    UnknownPart(String),
+    #[error("Part {0} is pinned at a rotation that is not a multiple of the rotation interval")]
+    UnalignedRotation(String),
+    #[error("Placing was cancelled")]
+    Cancelled,
+    #[error("No solutions found within the time limit")]
+    TimedOut,
+    #[error("Could not start placing threads: {0}")]
+    ThreadPool(#[from] ThreadPoolBuildError),
 }
 
 #[derive(Clone)]
@@ -89,8 +388,7 @@ pub fn default_sort_modes() -> Vec<SortMode> {
     modes.push(SortMode::WidthDec);
     modes.push(SortMode::HeightDec);
 
-    let mut rng = rand::thread_rng();
-    modes.push(SortMode::Shuffle(rng.gen()));
+    modes.push(SortMode::Shuffle(0));
 
     modes
 }
@@ -112,7 +410,11 @@ impl Request {
             plate_shape: boxed_plate_shape,
             single_plate_mode: true,
             sort_modes: default_sort_modes(),
-            max_threads: 1,
+            custom_order: vec![],
+            gravities: vec![Gravity::Original],
+            rotate_directions: vec![RotateDirection::CounterClockwise],
+            max_threads: 0,
+            thread_pool: OnceLock::new(),
             precision: 0.5 * resolution,
             spacing: 1.5 * resolution,
             delta: 1.0 * resolution,
@@ -123,6 +425,20 @@ impl Request {
             center_x: center_x * resolution,
             center_y: center_y * resolution,
             timeout: None,
+            scorer: None,
+            layout_mode: LayoutMode::Compact,
+            quality_map: None,
+            seed: rand::thread_rng().gen(),
+            distribution: Distribution::FirstFit,
+            plate_count: None,
+            plate_limits: PlateLimits::default(),
+            sequential: None,
+            hole_nesting: HoleNesting::Allow,
+            anchor: Anchor::Auto,
+            search_policy: SearchPolicy::balanced(),
+            expansion_direction: ExpansionDirection::Right,
+            objective: Objective::default(),
+            cancellation: CancellationToken::new(),
         }
     }
 
@@ -142,18 +458,206 @@ impl Request {
         self.precision = precision * self.resolution;
     }
 
+    pub fn set_single_plate_mode(&mut self, single_plate_mode: bool) {
+        self.single_plate_mode = single_plate_mode;
+    }
+
+    // set_distribution sets how parts are shared out over the plates. A max_height_ratio
+    // below 1 would give every part a plate of its own, so it is raised to 1.
+    pub fn set_distribution(&mut self, distribution: Distribution) {
+        self.distribution = match distribution {
+            Distribution::HeightGrouped { max_height_ratio } => Distribution::HeightGrouped {
+                max_height_ratio: f64::max(max_height_ratio, 1.0),
+            },
+            distribution => distribution,
+        };
+    }
+
+    // set_plate_count fixes the number of plates a balanced distribution uses. More
+    // plates are added if the parts do not fit. Without a count, the fewest plates
+    // that first fit placement needs are used.
+    pub fn set_plate_count(&mut self, plate_count: usize) {
+        self.plate_count = Some(plate_count);
+    }
+
+    // set_plate_limits caps the parts on each plate. Parts that would take a plate over
+    // a limit go on another plate, or are reported as unplaced by the solution.
+    pub fn set_plate_limits(&mut self, limits: PlateLimits) {
+        let resolution = self.resolution;
+        self.plate_limits = PlateLimits {
+            max_parts: limits.max_parts,
+            max_volume: limits.max_volume.map(|v| v * resolution.powi(3)),
+            max_footprint: limits.max_footprint.map(|a| a * resolution.powi(2)),
+        };
+    }
+
+    // set_sequential lays parts out to be printed one at a time. Parts taller than the
+    // gantry get a keep-out for the carriage, and plates report a print order that
+    // finishes them last.
+    pub fn set_sequential(&mut self, sequential: SequentialPrinting) {
+        self.sequential = Some(sequential);
+        self.prepare_parts();
+    }
+
+    // set_anchor sets where the layout is moved to on each plate, or on the expanded
+    // plate when the parts do not fit on one.
+    pub fn set_anchor(&mut self, anchor: Anchor) {
+        self.anchor = anchor;
+    }
+
+    pub fn set_search_policy(&mut self, search_policy: SearchPolicy) {
+        self.search_policy = search_policy;
+    }
+
+    pub fn set_expansion_direction(&mut self, expansion_direction: ExpansionDirection) {
+        self.expansion_direction = expansion_direction;
+    }
+
+    pub fn set_objective(&mut self, objective: Objective) {
+        self.objective = objective;
+    }
+
+    // set_cancellation_token sets the token that stops placing when cancelled. The
+    // runners then return the best solution found so far.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation = token;
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    pub fn set_hole_nesting(&mut self, hole_nesting: HoleNesting) {
+        self.hole_nesting = hole_nesting;
+        self.prepare_parts();
+    }
+
+    // expansion_steps is the number of larger plate sizes the search may try.
+    pub(crate) fn expansion_steps(&self) -> usize {
+        let length = self.expansion_direction.length(self.plate_shape.as_ref());
+        self.search_policy.expansion_steps(length, self.resolution)
+    }
+
+    // expanded_plate_shape is the plate grown by the given number of expansion steps.
+    pub(crate) fn expanded_plate_shape(&self, steps: usize) -> Box<dyn PlateShape> {
+        let shape = &self.plate_shape;
+        let length = self.expansion_direction.length(shape.as_ref());
+        let factor = self.search_policy.expansion_factor(steps, length, self.resolution);
+
+        match self.expansion_direction {
+            ExpansionDirection::Right => shape.extend_right(factor),
+            ExpansionDirection::Up => shape.extend_up(factor),
+            ExpansionDirection::Proportional => shape.extend(factor),
+        }
+    }
+
+    fn prepare_parts(&mut self) {
+        let mut parts = std::mem::take(&mut self.parts);
+        match self.thread_pool() {
+            Ok(pool) => pool.install(|| {
+                parts
+                    .par_iter_mut()
+                    .for_each(|(_, part)| self.prepare_part(part))
+            }),
+            // Without threads, as on the web, the parts are prepared one at a time
+            Err(_) => parts.values_mut().for_each(|part| self.prepare_part(part)),
+        }
+        self.parts = parts;
+    }
+
+    // prepare_part fills the holes of the part that parts may not be nested in, and
+    // gives it a carriage keep-out if it is too tall to pass under the gantry when
+    // printing sequentially.
+    pub(crate) fn prepare_part(&self, part: &mut Part) {
+        let min_hole = match self.hole_nesting {
+            _ if part.get_fill_holes() => usize::MAX,
+            HoleNesting::Allow => 0,
+            HoleNesting::Forbid => usize::MAX,
+            HoleNesting::MinHoleArea(area) => {
+                f64::ceil(area * f64::powi(self.resolution / self.precision, 2)) as usize
+            }
+        };
+
+        let keep_out = self.sequential.and_then(|sequential| {
+            if part.model_height <= sequential.gantry_height * self.resolution {
+                return None;
+            }
+
+            let to_pixels = |mm: f64| f64::ceil(mm * self.resolution / self.precision) as i32;
+            let (left, right, front, back) = sequential.carriage_extents;
+            Some((to_pixels(left), to_pixels(right), to_pixels(front), to_pixels(back)))
+        });
+
+        part.set_footprints(min_hole, keep_out);
+    }
+
     pub fn set_sort_modes(&mut self, sort_modes: Vec<SortMode>) {
         self.sort_modes = sort_modes;
     }
 
+    // set_custom_order sets the part IDs that SortMode::Custom places first, in order.
+    pub fn set_custom_order(&mut self, order: Vec<String>) {
+        self.custom_order = order;
+    }
+
+    pub fn set_gravities(&mut self, gravities: Vec<Gravity>) {
+        self.gravities = gravities;
+    }
+
+    pub fn set_rotate_directions(&mut self, rotate_directions: Vec<RotateDirection>) {
+        self.rotate_directions = rotate_directions;
+    }
+
+    // set_shuffle_count replaces the shuffled sort modes with count independently
+    // seeded shuffles, each of which is attempted by its own placer.
+    pub fn set_shuffle_count(&mut self, count: usize) {
+        self.sort_modes
+            .retain(|mode| !matches!(mode, SortMode::Shuffle(_)));
+        self.sort_modes.extend((0..count).map(SortMode::Shuffle));
+    }
+
     pub fn set_max_threads(&mut self, max_threads: usize) {
         self.max_threads = max_threads;
+        self.thread_pool = OnceLock::new();
+    }
+
+    // thread_pool returns the pool of max_threads threads that the request's parallel
+    // work runs in.
+    pub(crate) fn thread_pool(&self) -> Result<&ThreadPool, PlacingError> {
+        if let Some(pool) = self.thread_pool.get() {
+            return Ok(pool);
+        }
+
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(self.max_threads)
+            .build()?;
+        Ok(self.thread_pool.get_or_init(|| pool))
     }
 
     pub fn set_timeout(&mut self, duration: Duration) {
         self.timeout = Some(duration);
     }
 
+    pub fn set_scorer(&mut self, scorer: Box<dyn PlacementScorer>) {
+        self.scorer = Some(scorer);
+    }
+
+    pub fn set_layout_mode(&mut self, layout_mode: LayoutMode) {
+        self.layout_mode = layout_mode;
+    }
+
+    pub fn set_quality_map(&mut self, quality_map: QualityMap) {
+        self.quality_map = Some(quality_map);
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn get_spacing(&self) -> f64 {
         self.spacing
     }
@@ -167,27 +671,136 @@ impl Request {
     }
 
     // TODO: replace option with explicit error handling (this is weird)
-    pub fn add_part(&mut self, part: Part) -> Option<()> {
+    pub fn add_part(&mut self, mut part: Part) -> Option<()> {
         let x = self.parts.get(part.id.as_str());
         if x.is_some() {
             return None;
         }
 
+        self.prepare_part(&mut part);
+
         let part_id = part.id.clone();
         self.parts.insert(part_id, part);
         Some(())
     }
 
+    // pin_plates keeps the parts of a previous arrangement, given as the placements
+    // on each plate, where they are. Only the remaining parts are placed, and extra
+    // plates or plate expansion are only used when they do not fit in the free space.
+    pub fn pin_plates(&mut self, plates: &[Vec<Placement>]) -> Result<(), PlacingError> {
+        for (plate, placements) in plates.iter().enumerate() {
+            self.pin_placements(plate, placements)?;
+        }
+
+        Ok(())
+    }
+
+    // pin_placements keeps the placed parts where they are on the given plate. In
+    // single plate mode all pinned parts share the one plate. Placements must be at a
+    // multiple of the rotation interval, as returned by Solution::get_placements.
+    pub fn pin_placements(
+        &mut self,
+        plate: usize,
+        placements: &[Placement],
+    ) -> Result<(), PlacingError> {
+        if let Some(unknown) = placements
+            .iter()
+            .find(|placement| !self.parts.contains_key(placement.get_id().as_str()))
+        {
+            return Err(PlacingError::UnknownPart(unknown.get_id()));
+        }
+
+        if let Some(unaligned) = placements.iter().find(|placement| {
+            let part = &self.parts[placement.get_id().as_str()];
+            part.rotation_index(placement.get_rotation()).is_none()
+        }) {
+            return Err(PlacingError::UnalignedRotation(unaligned.get_id()));
+        }
+
+        for placement in placements {
+            let part = self.parts.get_mut(placement.get_id().as_str()).unwrap();
+            part.pin(plate, placement).unwrap();
+        }
+
+        Ok(())
+    }
+
+    // clear_pins allows every unlocked part to move again.
+    pub fn clear_pins(&mut self) {
+        for part in self.parts.values_mut() {
+            part.unpin();
+        }
+    }
+
     pub(crate) fn get_placers_for_spiral_place(&self) -> Vec<Placer> {
         let mut placers = vec![];
-        let sort_modes = Vec::clone(&self.sort_modes);
-
-        for sort_mode in sort_modes {
+        let configs = self.sort_modes.iter().flat_map(|sort_mode| {
+            self.gravities.iter().flat_map(move |gravity| {
+                self.rotate_directions
+                    .iter()
+                    .map(move |direction| (sort_mode, gravity, direction))
+            })
+        });
+
+        for (index, (sort_mode, gravity, direction)) in configs.enumerate() {
             let mut placer = Placer::new(self);
-            placer.sort_parts(sort_mode);
+            placer.set_index(index);
+            placer.set_gravity(*gravity);
+            placer.set_rotate_direction(*direction);
+            placer.sort_parts(*sort_mode);
             placers.push(placer)
         }
 
         placers
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::part::Part;
+    use crate::plater::placement::Placement;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::point::Point;
+    use crate::plater::request::{
+        Algorithm, BedExpansionMode, ConfigOrder, PlacingError, PointEnumerationMode, Request,
+        Strategy,
+    };
+
+    #[test]
+    fn pins_must_be_at_a_rotation_step() {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(60.0, 60.0, 1.0);
+        let mut request = Request::new(shape, 1.0, algorithm, 30.0, 30.0);
+        let bitmap = Bitmap::new_bitmap_with_data(10, 10, &[1; 100]).unwrap();
+        let part = Part::new(
+            String::from("p0"),
+            bitmap,
+            0.0,
+            0.0,
+            request.precision,
+            request.delta_r,
+            request.spacing,
+            60.0,
+            60.0,
+            false,
+        )
+        .unwrap();
+        request.add_part(part).unwrap();
+        let delta_r = request.get_delta_r();
+        let pin = |rotation: f64| {
+            Placement::new(String::from("p0"), Point::new(20.0, 20.0), rotation)
+        };
+
+        assert!(request.pin_placements(0, &[pin(3.0 * delta_r)]).is_ok());
+        assert!(matches!(
+            request.pin_placements(0, &[pin(delta_r / 3.0)]),
+            Err(PlacingError::UnalignedRotation(id)) if id == "p0"
+        ));
+    }
+}
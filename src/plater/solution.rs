@@ -1,13 +1,27 @@
 use std::fmt::{Debug, Formatter};
 
 use crate::plater::placed_part::PlacedPart;
+use crate::plater::placement::Placement;
+use crate::plater::placer::{plate_height, plate_load, PlacerConfig};
 use crate::plater::plate::Plate;
-use crate::plater::request::PlacingError;
+use crate::plater::plate_shape::PlateShape;
+use crate::plater::request::{Anchor, LoadMetric, Objective, PlacingError, SearchPolicy};
 
 #[derive(Clone)]
 pub struct Solution<'a> {
     plates: Vec<Plate<'a>>,
     pub best_so_far: Option<usize>,
+    // seed and config record how the solution was produced, so it can be reproduced
+    pub(crate) seed: Option<u64>,
+    pub(crate) config: Option<PlacerConfig>,
+    pub(crate) search_policy: Option<SearchPolicy>,
+    // unplaced holds the IDs of the parts that were left out, either to stay within the
+    // plate limits or, in multi plate mode, because they do not fit on an empty plate
+    pub(crate) unplaced: Vec<String>,
+    // imbalance is the spread between the most and least loaded plates
+    pub(crate) imbalance: f64,
+    // time_limited records that the time limit ran out before every placer finished
+    pub(crate) time_limited: bool,
 }
 
 impl<'a> Debug for Solution<'a> {
@@ -16,25 +30,25 @@ impl<'a> Debug for Solution<'a> {
     }
 }
 
-pub(crate) fn get_smallest_solution<'solutions, 'part>(
-    solutions: &'solutions mut Vec<Solution<'part>>,
+// get_best_solution picks the solution that places the most parts, and then ranks
+// best by the objective.
+pub(crate) fn get_best_solution<'part>(
+    solutions: &mut Vec<Solution<'part>>,
+    objective: &Objective,
 ) -> Result<Solution<'part>, PlacingError> {
-    let mut smallest_area = None;
-    let mut best_solution = None;
-
-    for (index, solution) in solutions.iter_mut().enumerate() {
-        let area = solution.plate_area();
-        if let Some(smallest_so_far) = &mut smallest_area {
-            if area < *smallest_so_far {
-                *smallest_so_far = area;
-            }
-
-            best_solution = Some(index);
-        } else {
-            smallest_area = Some(area);
-            best_solution = Some(index);
-        }
-    }
+    // Ties are broken by placer index so the choice does not depend on the order
+    // in which placers finished
+    let best_solution = solutions
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            a.unplaced
+                .len()
+                .cmp(&b.unplaced.len())
+                .then_with(|| objective.compare(a, b))
+                .then_with(|| a.placer_index().cmp(&b.placer_index()))
+        })
+        .map(|(index, _)| index);
 
     match best_solution {
         None => Err(PlacingError::NoSolutionFound),
@@ -47,6 +61,12 @@ impl<'a> Solution<'a> {
         Solution {
             plates: vec![],
             best_so_far: None,
+            seed: None,
+            config: None,
+            search_policy: None,
+            unplaced: vec![],
+            imbalance: 0.0,
+            time_limited: false,
         }
     }
 
@@ -73,6 +93,70 @@ impl<'a> Solution<'a> {
         plate.width * plate.height
     }
 
+    // bounding_box_area sums the area of the bounding box of the parts on each plate.
+    pub fn bounding_box_area(&self) -> f64 {
+        self.plates.iter().map(Plate::parts_bounding_box_area).sum()
+    }
+
+    // hull_area sums the area of the convex hull of the parts on each plate.
+    pub fn hull_area(&self) -> f64 {
+        self.plates.iter().map(Plate::parts_hull_area).sum()
+    }
+
+    fn placer_index(&self) -> usize {
+        self.config
+            .as_ref()
+            .map_or(usize::MAX, |config| config.placer_index)
+    }
+
+    // get_seed returns the request seed the solution was produced with.
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    // print_height sums the height of the tallest part on each plate, in resolution
+    // units. The number of layers to print, and so the print time, grows with it.
+    pub fn print_height(&self) -> f64 {
+        self.plates.iter().map(plate_height).sum()
+    }
+
+    // get_unplaced returns the IDs of the parts that could not be placed without going
+    // over the plate limits, or that do not fit on an empty plate in multi plate mode.
+    pub fn get_unplaced(&self) -> &[String] {
+        self.unplaced.as_slice()
+    }
+
+    // get_search_policy returns the plate search policy the solution was found with.
+    pub fn get_search_policy(&self) -> Option<&SearchPolicy> {
+        self.search_policy.as_ref()
+    }
+
+    // is_time_limited returns whether placing stopped at the time limit, in which case
+    // a better solution may have been found with more time.
+    pub fn is_time_limited(&self) -> bool {
+        self.time_limited
+    }
+
+    // get_imbalance returns the difference in load between the most and least loaded
+    // plates, measured by the metric of a balanced distribution or else by footprint.
+    pub fn get_imbalance(&self) -> f64 {
+        self.imbalance
+    }
+
+    // update_imbalance measures the spread between the plate loads under the metric.
+    pub(crate) fn update_imbalance(&mut self, metric: LoadMetric) {
+        let loads = self.plates.iter().map(|plate| plate_load(plate, metric));
+        let (min, max) = loads.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), load| {
+            (f64::min(min, load), f64::max(max, load))
+        });
+        self.imbalance = if self.plates.is_empty() { 0.0 } else { max - min };
+    }
+
+    // get_config returns the configuration of the placer that produced the solution.
+    pub fn get_config(&self) -> Option<&PlacerConfig> {
+        self.config.as_ref()
+    }
+
     pub(crate) fn dims(&self) -> (f64, f64) {
         let plate = self.get_last_plate();
         (plate.width, plate.height)
@@ -90,6 +174,12 @@ impl<'a> Solution<'a> {
         self.plates.as_slice()
     }
 
+    // get_placements returns the placements on each plate, which can be pinned to
+    // add parts to the arrangement later.
+    pub fn get_placements(&self) -> Vec<Vec<Placement>> {
+        self.plates.iter().map(|plate| plate.get_placements()).collect()
+    }
+
     pub(crate) fn get_plate_mut<'b>(&'b mut self, n: usize) -> Option<&'b mut Plate<'a>> {
         self.plates.get_mut(n)
     }
@@ -98,7 +188,91 @@ impl<'a> Solution<'a> {
         self.get_plate(self.plates.len() - 1).unwrap()
     }
 
+    // anchor_plates moves the layout on each plate to the anchor point. Plates larger
+    // than the original plate shape were expanded.
+    pub(crate) fn anchor_plates(&mut self, anchor: Anchor, original: &dyn PlateShape) {
+        for plate in &mut self.plates {
+            let expanded = (plate.width > original.width(), plate.height > original.height());
+            plate.anchor(anchor, expanded);
+        }
+    }
+
+    // is_anchored returns whether the layout on every plate was moved to the anchor point.
+    pub fn is_anchored(&self) -> bool {
+        self.plates.iter().all(Plate::is_anchored)
+    }
+
     pub(crate) fn add_plate(&mut self, plate: Plate<'a>) {
         self.plates.push(plate);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::bitmap::Bitmap;
+    use crate::plater::part::Part;
+    use crate::plater::placed_part::PlacedPart;
+    use crate::plater::plate::Plate;
+    use crate::plater::plate_shape::Shape;
+    use crate::plater::request::{
+        Algorithm, BedExpansionMode, ConfigOrder, LoadMetric, Objective, PointEnumerationMode,
+        Request, Strategy,
+    };
+    use crate::plater::solution::{get_best_solution, Solution};
+
+    #[test]
+    fn best_balance_prefers_evenly_loaded_plates() {
+        let algorithm = Algorithm {
+            strategy: Strategy::PixelPack,
+            order_config: ConfigOrder::PointFirst,
+            point_enumeration_mode: PointEnumerationMode::Row,
+            bed_expansion_mode: BedExpansionMode::Exponential,
+        };
+        let shape = Shape::new_rectangle(60.0, 60.0, 1.0);
+        let request = Request::new(shape, 1.0, algorithm, 30.0, 30.0);
+        let part = |id: &str, size: i32| {
+            let pixels = vec![1; (size * size) as usize];
+            let bitmap = Bitmap::new_bitmap_with_data(size, size, &pixels).unwrap();
+            Part::new(
+                id.to_string(),
+                bitmap,
+                0.0,
+                0.0,
+                request.precision,
+                request.delta_r,
+                request.spacing,
+                60.0,
+                60.0,
+                false,
+            )
+            .unwrap()
+        };
+        let parts = [part("p0", 40), part("p1", 20), part("p2", 20)];
+
+        // Both layouts put the same parts on two plates, and only differ in which plate
+        // the second small part is on
+        let layout = |plate_of: [usize; 3]| {
+            let mut plates = vec![];
+            for _ in 0..2 {
+                plates.push(Plate::new(request.plate_shape.as_ref(), request.precision, 30.0, 30.0));
+            }
+            for (part, plate) in parts.iter().zip(plate_of) {
+                plates[plate].place(PlacedPart::new_placed_part(part));
+            }
+
+            let mut solution = Solution::new();
+            for plate in plates {
+                solution.add_plate(plate);
+            }
+            solution.update_imbalance(LoadMetric::Footprint);
+            solution
+        };
+        let lopsided = layout([0, 0, 1]);
+        let balanced = layout([0, 1, 1]);
+        assert!(balanced.get_imbalance() < lopsided.get_imbalance());
+
+        let mut solutions = vec![lopsided, balanced.clone()];
+        let best = get_best_solution(&mut solutions, &Objective::BestBalance).unwrap();
+        assert_eq!(best.get_imbalance(), balanced.get_imbalance());
+    }
+}
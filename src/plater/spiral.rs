@@ -186,30 +186,21 @@ impl<A, T: Iterator<Item = A>, const N: usize> Iterator for WindowIter<A, T, N>
 #[derive(Ord, Eq, PartialOrd, PartialEq)]
 struct PairWrapper<A, B>((A, B));
 
+// spiral_iterator enumerates the points of a width x height area, delta apart,
+// spiralling outwards from origin.
 pub(crate) fn spiral_iterator(
     delta: f64,
     width: f64,
     height: f64,
-    original_width: f64,
-    original_height: f64,
+    origin: (f64, f64),
 ) -> impl Iterator<Item = (f64, f64)> {
     let d_width = f64::floor(width / delta) as isize;
     let d_height = f64::floor(height / delta) as isize;
 
-    let origin = {
-        let (w, h) = {
-            if original_width < width {
-                (original_width, original_height)
-            } else {
-                (width, height)
-            }
-        };
-
-        (
-            f64::floor(w / delta) as isize,
-            f64::floor(h / delta) as isize,
-        )
-    };
+    let origin = (
+        (f64::floor(origin.0 / delta) as isize).clamp(0, d_width),
+        (f64::floor(origin.1 / delta) as isize).clamp(0, d_height),
+    );
 
     let rect = Rectangle {
         x_range: InclusiveRange {
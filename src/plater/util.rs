@@ -10,3 +10,71 @@ pub(crate) fn apply_rotation(point: (f64, f64), angle: f64) -> (i32, i32) {
     let (x, y) = util::apply_rotation_f64(point, angle);
     (f64::ceil(x) as i32, f64::ceil(y) as i32)
 }
+
+// derive_seed mixes a stream number into a seed (SplitMix64), so that every
+// random choice made for a request gets an independent, reproducible seed.
+pub(crate) fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// convex_hull returns the convex hull of the points in counter-clockwise order,
+// using Andrew's monotone chain algorithm.
+pub(crate) fn convex_hull(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| f64::total_cmp(&a.0, &b.0).then(f64::total_cmp(&a.1, &b.1)));
+    points.dedup();
+
+    if points.len() < 3 {
+        return points;
+    }
+
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(2 * points.len());
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for point in pass {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last point of each pass is the first point of the next one
+        hull.pop();
+    }
+
+    hull
+}
+
+// polygon_area returns the area enclosed by the polygon (shoelace formula).
+pub(crate) fn polygon_area(points: &[(f64, f64)]) -> f64 {
+    let n = points.len();
+    let twice_area: f64 = (0..n)
+        .map(|i| {
+            let (x1, y1) = points[i];
+            let (x2, y2) = points[(i + 1) % n];
+            x1 * y2 - x2 * y1
+        })
+        .sum();
+
+    f64::abs(twice_area) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::plater::util::{convex_hull, polygon_area};
+
+    #[test]
+    fn convex_hull_drops_interior_points() {
+        let points = vec![(0.0, 0.0), (2.0, 0.0), (1.0, 1.0), (2.0, 2.0), (0.0, 2.0), (1.0, 0.5)];
+        let hull = convex_hull(points);
+        assert_eq!(hull, vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+        assert_eq!(polygon_area(&hull), 4.0);
+    }
+}
@@ -46,6 +46,10 @@ impl Model {
             .unwrap_or_else(|| Point3D::new(0.0, 0.0, 0.0))
     }
 
+    pub(crate) fn volume(&self) -> f64 {
+        self.volumes.iter().map(Volume::volume).sum()
+    }
+
     fn initialize_quad_tree(&mut self) {
         let min_p = self.min();
         let max_p = self.max();
@@ -32,7 +32,7 @@ pub(crate) fn load_model(
     let center_x = (min.x + max.x) / 2.0;
     let center_y = (min.y + max.y) / 2.0;
 
-    let part = plater::part::Part::new(
+    let mut part = plater::part::Part::new(
         id,
         bitmap,
         center_x,
@@ -45,6 +45,8 @@ pub(crate) fn load_model(
         locked,
     )
     .ok()?;
+    part.set_model_height(max.z - min.z);
+    part.set_volume(next_model.volume());
 
     Some((part, next_model))
 }
@@ -73,7 +73,7 @@ impl Request {
 
         let n = filename.to_owned();
 
-        let (part, model) = load_model(
+        let (mut part, model) = load_model(
             filename,
             id.to_owned(),
             self.resolution,
@@ -86,6 +86,7 @@ impl Request {
             locked,
         )?;
 
+        self.request.prepare_part(&mut part);
         self.models.insert(id.to_owned(), model);
         self.request.parts.insert(id, part);
 
@@ -38,4 +38,20 @@ impl Volume {
     pub(crate) fn max(&self) -> Point3D {
         self.reduce_faces_with(|x, y| Point3D::max(&x, &y))
     }
+
+    // volume returns the enclosed volume of the mesh, which should be closed, as
+    // the sum of the signed volumes of the tetrahedra from the origin to each face.
+    pub(crate) fn volume(&self) -> f64 {
+        let signed: f64 = self
+            .faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = &face.v;
+                let cross = b.cross_product(c);
+                (a.x * cross.x + a.y * cross.y + a.z * cross.z) / 6.0
+            })
+            .sum();
+
+        f64::abs(signed)
+    }
 }